@@ -1,58 +1,27 @@
-use std::net::TcpStream;
-use std::io::{Read, Write};
-use std::str::from_utf8;
-use std::{error::Error, fs};
 use std::env;
+use std::error::Error;
+use std::path::Path;
+
+use sui_ctf_framework::client::Client;
 
 fn main() -> Result<(), Box<dyn Error>> {
 
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()); // replace with remote ip
-    let port = env::var("PORT").unwrap_or_else(|_| "31337".to_string());
-
-    match TcpStream::connect(format!("{}:{}", host, port)) {
-        Ok(mut stream) => {
-            println!("  - Connected!");
-
-            let mod_data : Vec<u8> = fs::read("./solve/build/gringotts_solution/bytecode_modules/gringotts_solution.mv").unwrap();
-            println!("  - Loaded solution!");
+    let port = env::var("PORT").unwrap_or_else(|_| "31337".to_string()).parse::<u16>()?;
 
-            stream.write_all(&mod_data)?;
-            stream.flush()?;
-            println!("  - Sent solution!");
+    sui_ctf_framework::logging::init()?;
+    let mut client = Client::connect(&host, port)?;
+    client.solve_pow()?;
+    if let Ok(token) = env::var("TEAM_TOKEN") {
+        client.send_team_token(&token)?;
+    }
+    println!("  - Connected!");
 
-            let mut return_data1 = [0 as u8; 200];
-            match stream.read(&mut return_data1) {
-                Ok(_) => {
-                    println!("  - Connection Output: '{}'", from_utf8(&return_data1).unwrap()); // Get module address
-                    let mut return_data2 = [0 as u8; 200];
-                    match stream.read(&mut return_data2) {
-                        Ok(_) => {
-                            println!("  - Connection Output: '{}'", from_utf8(&return_data2).unwrap()); // Get module address
-                            let mut flag = [0 as u8; 200]; 
-                            match stream.read(&mut flag) {
-                                Ok(_) => {
-                                    println!("  - Connection Output: '{}'", from_utf8(&flag).unwrap()); // Get flag
+    client.upload_package(Path::new("./solve/build/gringotts_solution"))?;
+    println!("  - Sent solution!");
 
-                                },
-                                Err(e) => {
-                                    println!("  - Failed to receive data: {}", e);
-                                }
-                            }
-                        },
-                        Err(e) => {
-                            println!("  - Failed to receive data: {}", e);
-                        }
-                    }
-                },
-                Err(e) => {
-                    println!("  - Failed to connect: {}", e);
-                }
-            }
-        },
-        Err(e) => {
-            println!("  - Failed to connect: {}", e);
-        }
-    }
+    // Module addresses, then the flag once the server closes the connection
+    println!("  - Connection Output: '{}'", client.read_to_end()?);
     println!("  - Terminated.");
 
     Ok(())
@@ -0,0 +1,253 @@
+use serde::Serialize;
+
+use move_binary_format::file_format::{
+    Ability, AbilitySet, CompiledModule, DatatypeHandleIndex, SignatureToken,
+    StructFieldInformation, Visibility,
+};
+use move_core_types::account_address::AccountAddress;
+
+// Structure of a published package, as seen from its compiled modules
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageAbi {
+    pub address: AccountAddress,
+    pub modules: Vec<ModuleAbi>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleAbi {
+    pub name: String,
+    pub structs: Vec<StructAbi>,
+    pub enums: Vec<EnumAbi>,
+    pub functions: Vec<FunctionAbi>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StructAbi {
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub type_params: Vec<TypeParamAbi>,
+    pub fields: Vec<FieldAbi>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnumAbi {
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub type_params: Vec<TypeParamAbi>,
+    pub variants: Vec<VariantAbi>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantAbi {
+    pub name: String,
+    pub fields: Vec<FieldAbi>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeParamAbi {
+    pub constraints: Vec<String>,
+    pub is_phantom: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldAbi {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionAbi {
+    pub name: String,
+    pub visibility: String,
+    pub is_entry: bool,
+    pub type_params: Vec<TypeParamAbi>,
+    pub parameters: Vec<String>,
+    pub returns: Vec<String>,
+}
+
+impl PackageAbi {
+    pub fn from_modules(
+        address: AccountAddress,
+        modules: &[CompiledModule]
+    ) -> PackageAbi {
+        PackageAbi {
+            address,
+            modules: modules.iter().map(ModuleAbi::from_module).collect(),
+        }
+    }
+
+    pub fn module(
+        &self,
+        name: &str
+    ) -> Option<&ModuleAbi> {
+        self.modules.iter().find(|m| m.name == name)
+    }
+
+    pub fn module_names(&self) -> Vec<String> {
+        self.modules.iter().map(|m| m.name.clone()).collect()
+    }
+}
+
+impl ModuleAbi {
+    pub fn from_module(module: &CompiledModule) -> ModuleAbi {
+        let structs = module.struct_defs().iter().map(|def| {
+            let handle = module.datatype_handle_at(def.struct_handle);
+            let fields = match &def.field_information {
+                StructFieldInformation::Native => Vec::new(),
+                StructFieldInformation::Declared(fields) => fields.iter().map(|field| FieldAbi {
+                    name: module.identifier_at(field.name).to_string(),
+                    type_: format_type(module, &field.signature.0),
+                }).collect(),
+            };
+
+            StructAbi {
+                name: module.identifier_at(handle.name).to_string(),
+                abilities: format_abilities(handle.abilities),
+                type_params: handle.type_parameters.iter().map(|param| TypeParamAbi {
+                    constraints: format_abilities(param.constraints),
+                    is_phantom: param.is_phantom,
+                }).collect(),
+                fields,
+            }
+        }).collect();
+
+        let enums = module.enum_defs().iter().map(|def| {
+            let handle = module.datatype_handle_at(def.enum_handle);
+            EnumAbi {
+                name: module.identifier_at(handle.name).to_string(),
+                abilities: format_abilities(handle.abilities),
+                type_params: handle.type_parameters.iter().map(|param| TypeParamAbi {
+                    constraints: format_abilities(param.constraints),
+                    is_phantom: param.is_phantom,
+                }).collect(),
+                variants: def.variants.iter().map(|variant| VariantAbi {
+                    name: module.identifier_at(variant.variant_name).to_string(),
+                    fields: variant.fields.iter().map(|field| FieldAbi {
+                        name: module.identifier_at(field.name).to_string(),
+                        type_: format_type(module, &field.signature.0),
+                    }).collect(),
+                }).collect(),
+            }
+        }).collect();
+
+        let functions = module.function_defs().iter().map(|def| {
+            let handle = module.function_handle_at(def.function);
+            let visibility = match def.visibility {
+                Visibility::Public => "public",
+                Visibility::Friend => "friend",
+                Visibility::Private => "private",
+            };
+
+            FunctionAbi {
+                name: module.identifier_at(handle.name).to_string(),
+                visibility: visibility.to_string(),
+                is_entry: def.is_entry,
+                type_params: handle.type_parameters.iter().map(|constraints| TypeParamAbi {
+                    constraints: format_abilities(*constraints),
+                    is_phantom: false,
+                }).collect(),
+                parameters: module.signature_at(handle.parameters).0.iter()
+                    .map(|token| format_type(module, token))
+                    .collect(),
+                returns: module.signature_at(handle.return_).0.iter()
+                    .map(|token| format_type(module, token))
+                    .collect(),
+            }
+        }).collect();
+
+        ModuleAbi {
+            name: module.name().to_string(),
+            structs,
+            enums,
+            functions,
+        }
+    }
+
+    pub fn function(
+        &self,
+        name: &str
+    ) -> Option<&FunctionAbi> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    pub fn struct_(
+        &self,
+        name: &str
+    ) -> Option<&StructAbi> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+
+    pub fn enum_(
+        &self,
+        name: &str
+    ) -> Option<&EnumAbi> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    // Functions a player can target directly from a transaction
+    pub fn callable_functions(&self) -> Vec<&FunctionAbi> {
+        self.functions.iter()
+            .filter(|f| f.is_entry || f.visibility == "public")
+            .collect()
+    }
+}
+
+fn format_abilities(abilities: AbilitySet) -> Vec<String> {
+    abilities.into_iter().map(|ability| {
+        match ability {
+            Ability::Copy => "copy",
+            Ability::Drop => "drop",
+            Ability::Store => "store",
+            Ability::Key => "key",
+        }.to_string()
+    }).collect()
+}
+
+fn format_datatype(
+    module: &CompiledModule,
+    idx: DatatypeHandleIndex,
+    type_args: &[SignatureToken]
+) -> String {
+    let handle = module.datatype_handle_at(idx);
+    let module_handle = module.module_handle_at(handle.module);
+    let address = module.address_identifier_at(module_handle.address);
+    let mut name = format!(
+        "{}::{}::{}",
+        address.to_hex_literal(),
+        module.identifier_at(module_handle.name),
+        module.identifier_at(handle.name)
+    );
+
+    if !type_args.is_empty() {
+        let args: Vec<String> = type_args.iter().map(|token| format_type(module, token)).collect();
+        name.push_str(&format!("<{}>", args.join(", ")));
+    }
+    name
+}
+
+pub fn format_type(
+    module: &CompiledModule,
+    token: &SignatureToken
+) -> String {
+    match token {
+        SignatureToken::Bool => "bool".to_string(),
+        SignatureToken::U8 => "u8".to_string(),
+        SignatureToken::U16 => "u16".to_string(),
+        SignatureToken::U32 => "u32".to_string(),
+        SignatureToken::U64 => "u64".to_string(),
+        SignatureToken::U128 => "u128".to_string(),
+        SignatureToken::U256 => "u256".to_string(),
+        SignatureToken::Address => "address".to_string(),
+        SignatureToken::Signer => "signer".to_string(),
+        SignatureToken::Vector(inner) => format!("vector<{}>", format_type(module, inner)),
+        SignatureToken::Datatype(idx) => format_datatype(module, *idx, &[]),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, type_args) = &**inst;
+            format_datatype(module, *idx, type_args)
+        }
+        SignatureToken::Reference(inner) => format!("&{}", format_type(module, inner)),
+        SignatureToken::MutableReference(inner) => format!("&mut {}", format_type(module, inner)),
+        SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use move_binary_format::file_format::{CompiledModule, FunctionDefinitionIndex, SignatureToken};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::FileHash;
+use move_core_types::account_address::AccountAddress;
+
+// Abort codes of `#[error]` constants (Move 2024) are laid out as
+// tag (1) | reserved (15) | line (16) | identifier index (16) | constant index (16),
+// the name of the constant being an identifier and its value a constant
+const CLEVER_ERROR_TAG: u64 = 1 << 63;
+const NO_CONSTANT: u16 = u16::MAX;
+
+// A Move abort decoded from an adapter error, e.g.
+//
+//   Transaction Effects Status: Move Runtime Abort. Location: challenge::merch_store::has_flag
+//   (function index 3) at offset 12, Abort Code: 1337
+//
+// `Display` gives a player-friendly message, `raw` keeps the adapter error as is.
+#[derive(Debug, Clone)]
+pub struct MoveAbort {
+    // Package address or named address as reported by the adapter
+    pub package: String,
+    pub address: Option<AccountAddress>,
+    pub module: String,
+    pub function: Option<String>,
+    pub function_index: u16,
+    pub offset: u16,
+    pub code: u64,
+    // Name of the constant the code comes from, e.g. `EINVALID_SOLUTION`
+    pub constant: Option<String>,
+    // Value of an `#[error]` constant, when it is a string
+    pub message: Option<String>,
+    pub source: Option<SourceLine>,
+    pub raw: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: usize,
+    pub text: String,
+}
+
+// Source file of a published module, used to turn source map locations into lines
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+}
+
+impl MoveAbort {
+    fn new(
+        package: String,
+        module: String,
+        function: Option<String>,
+        function_index: u16,
+        offset: u16,
+        code: u64,
+        raw: &str
+    ) -> Self {
+        let literal = if package.starts_with("0x") { package.clone() } else { format!("0x{}", package) };
+        MoveAbort {
+            address: AccountAddress::from_hex_literal(&literal).ok(),
+            package,
+            module,
+            function,
+            function_index,
+            offset,
+            code,
+            constant: None,
+            message: None,
+            source: None,
+            raw: raw.to_string(),
+        }
+    }
+
+    // Extract the abort location and code from an adapter error, `None` when the
+    // error is not a Move abort
+    pub fn parse(error: &str) -> Option<MoveAbort> {
+        parse_display(error).or_else(|| parse_debug(error))
+    }
+
+    // Fill in the function name, constant name and source line from the aborting
+    // module and its source map
+    pub fn resolve(
+        &mut self,
+        module: &CompiledModule,
+        source_map: Option<&SourceMap>,
+        sources: &HashMap<FileHash, SourceFile>
+    ) {
+        if self.function.is_none() {
+            self.function = module
+                .function_defs
+                .get(self.function_index as usize)
+                .map(|def| module.identifier_at(module.function_handle_at(def.function).name).to_string());
+        }
+
+        if self.code & CLEVER_ERROR_TAG != 0 {
+            self.resolve_clever_error(module, source_map, sources);
+            return;
+        }
+
+        // Plain codes are matched against the module's `u64` constants, names come
+        // from the source map as compiled modules do not keep them
+        if let Some(source_map) = source_map {
+            self.constant = module
+                .constant_pool
+                .iter()
+                .enumerate()
+                .filter(|(_, constant)| constant.type_ == SignatureToken::U64)
+                .filter(|(_, constant)| bcs::from_bytes::<u64>(&constant.data).ok() == Some(self.code))
+                .find_map(|(index, _)| {
+                    source_map
+                        .constant_map
+                        .iter()
+                        .find(|(_, idx)| **idx as usize == index)
+                        .map(|(name, _)| name.to_string())
+                });
+
+            self.source = source_map
+                .get_code_location(FunctionDefinitionIndex(self.function_index), self.offset)
+                .ok()
+                .and_then(|loc| {
+                    let file = sources.get(&loc.file_hash())?;
+                    let line = file.text[..(loc.start() as usize).min(file.text.len())].matches('\n').count() + 1;
+                    Some(source_line(file, line))
+                });
+        }
+    }
+
+    fn resolve_clever_error(
+        &mut self,
+        module: &CompiledModule,
+        source_map: Option<&SourceMap>,
+        sources: &HashMap<FileHash, SourceFile>
+    ) {
+        let (line, identifier_index, constant_index) = clever_error_parts(self.code);
+
+        if identifier_index != NO_CONSTANT {
+            self.constant = module.identifiers.get(identifier_index as usize).map(|name| name.to_string());
+        }
+        if constant_index != NO_CONSTANT {
+            self.message = module
+                .constant_pool
+                .get(constant_index as usize)
+                .filter(|constant| constant.type_ == SignatureToken::Vector(Box::new(SignatureToken::U8)))
+                .and_then(|constant| bcs::from_bytes::<Vec<u8>>(&constant.data).ok())
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+        }
+
+        self.source = source_map
+            .and_then(|map| sources.get(&map.definition_location.file_hash()))
+            .map(|file| source_line(file, line as usize));
+    }
+
+    // Abort code as written in the source, the line number of `#[error]` codes is
+    // only shown in the location
+    pub fn describe_code(&self) -> String {
+        let code = if self.code & CLEVER_ERROR_TAG != 0 {
+            format!("{:#x}", self.code)
+        } else {
+            self.code.to_string()
+        };
+        match (&self.constant, &self.message) {
+            (Some(name), Some(message)) => format!("{} ({}: {})", code, name, message),
+            (Some(name), None) => format!("{} ({})", code, name),
+            (None, _) => code,
+        }
+    }
+}
+
+impl fmt::Display for MoveAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Aborted in {}::", self.module)?;
+        match &self.function {
+            Some(function) => write!(f, "{}", function)?,
+            None => write!(f, "<function {}>", self.function_index)?,
+        }
+        write!(f, " at offset {} with code {}", self.offset, self.describe_code())?;
+        if let Some(source) = &self.source {
+            write!(f, "\n  --> {}:{}\n   | {}", source.file, source.line, source.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for MoveAbort {}
+
+// Source line, identifier index and constant index of an `#[error]` abort code
+pub fn clever_error_parts(code: u64) -> (u16, u16, u16) {
+    (
+        ((code >> 32) & 0xffff) as u16,
+        ((code >> 16) & 0xffff) as u16,
+        (code & 0xffff) as u16,
+    )
+}
+
+fn source_line(
+    file: &SourceFile,
+    line: usize
+) -> SourceLine {
+    SourceLine {
+        file: file.name.clone(),
+        line,
+        text: file.text.lines().nth(line.saturating_sub(1)).unwrap_or_default().trim().to_string(),
+    }
+}
+
+// `Move Runtime Abort. Location: <addr>::<module>::<function> (function index N) at offset M, Abort Code: C`,
+// or `Location: <addr>::<module> in function definition N at offset M` without a function name
+fn parse_display(error: &str) -> Option<MoveAbort> {
+    let rest = error.split_once("Move Runtime Abort. Location: ")?.1;
+    let (location, rest) = rest.split_once(", Abort Code: ")?;
+    let code = leading_number(rest)?;
+
+    let (path, function_index, offset) = if let Some((path, rest)) = location.split_once(" (function index ") {
+        let (index, rest) = rest.split_once(") at offset ")?;
+        (path, index.parse().ok()?, leading_number(rest)? as u16)
+    } else {
+        let (path, rest) = location.split_once(" in function definition ")?;
+        let (index, rest) = rest.split_once(" at offset ")?;
+        (path, index.parse().ok()?, leading_number(rest)? as u16)
+    };
+
+    let mut parts: Vec<&str> = path.split("::").collect();
+    let function = if parts.len() == 3 { parts.pop().map(str::to_string) } else { None };
+    let (package, module) = match parts.as_slice() {
+        [package, module] => (package.to_string(), module.to_string()),
+        _ => return None,
+    };
+
+    Some(MoveAbort::new(package, module, function, function_index, offset, code, error))
+}
+
+// `MoveAbort(MoveLocation { module: ModuleId { address: A, name: Identifier("m") }, function: N,
+// instruction: M, function_name: Some("f") }, C)`
+fn parse_debug(error: &str) -> Option<MoveAbort> {
+    let rest = error.split_once("MoveAbort(MoveLocation { ")?.1;
+    let package = rest.split_once("address: ")?.1.split(|c| c == ',' || c == ' ').next()?.to_string();
+    let module = rest.split_once("name: Identifier(\"")?.1.split('"').next()?.to_string();
+    let function_index = leading_number(rest.split_once("function: ")?.1)? as u16;
+    let offset = leading_number(rest.split_once("instruction: ")?.1)? as u16;
+    let function = rest
+        .split_once("function_name: Some(\"")
+        .and_then(|(_, name)| name.split('"').next())
+        .map(str::to_string);
+    // The code follows the location, whose module id holds a `}, ` of its own
+    let code = leading_number(rest.split_once("instruction: ")?.1.split_once("}, ")?.1)?;
+
+    Some(MoveAbort::new(package, module, function, function_index, offset, code, error))
+}
+
+fn leading_number(text: &str) -> Option<u64> {
+    let digits: String = text.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{empty_module, Constant};
+    use move_core_types::identifier::Identifier;
+
+    const DISPLAY: &str = "Transaction Effects Status: Move Runtime Abort. Location: \
+        0000000000000000000000000000000000000000000000000000000000000005::merch_store::has_flag \
+        (function index 3) at offset 12, Abort Code: 1337";
+    const DEBUG: &str = "Error executing transaction: MoveAbort(MoveLocation { module: ModuleId { \
+        address: 0000000000000000000000000000000000000000000000000000000000000005, name: Identifier(\"pool\") }, \
+        function: 2, instruction: 7, function_name: Some(\"swap\") }, 42) in command 0";
+
+    fn clever_code(
+        line: u16,
+        identifier_index: u16,
+        constant_index: u16
+    ) -> u64 {
+        CLEVER_ERROR_TAG | (line as u64) << 32 | (identifier_index as u64) << 16 | constant_index as u64
+    }
+
+    #[test]
+    fn parses_display_aborts() {
+        let abort = MoveAbort::parse(DISPLAY).unwrap();
+        assert_eq!(abort.address, Some(AccountAddress::from_hex_literal("0x5").unwrap()));
+        assert_eq!(abort.module, "merch_store");
+        assert_eq!(abort.function.as_deref(), Some("has_flag"));
+        assert_eq!((abort.function_index, abort.offset, abort.code), (3, 12, 1337));
+        assert_eq!(abort.raw, DISPLAY);
+        assert_eq!(abort.to_string(), "Aborted in merch_store::has_flag at offset 12 with code 1337");
+    }
+
+    #[test]
+    fn parses_display_aborts_without_function_name() {
+        let error = "Move Runtime Abort. Location: 0x5::pool in function definition 4 at offset 9, Abort Code: 0";
+        let abort = MoveAbort::parse(error).unwrap();
+        assert_eq!(abort.package, "0x5");
+        assert_eq!(abort.module, "pool");
+        assert_eq!(abort.function, None);
+        assert_eq!((abort.function_index, abort.offset, abort.code), (4, 9, 0));
+        assert_eq!(abort.to_string(), "Aborted in pool::<function 4> at offset 9 with code 0");
+    }
+
+    #[test]
+    fn parses_debug_aborts() {
+        let abort = MoveAbort::parse(DEBUG).unwrap();
+        assert_eq!(abort.address, Some(AccountAddress::from_hex_literal("0x5").unwrap()));
+        assert_eq!(abort.module, "pool");
+        assert_eq!(abort.function.as_deref(), Some("swap"));
+        assert_eq!((abort.function_index, abort.offset, abort.code), (2, 7, 42));
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        assert!(MoveAbort::parse("Transaction Effects Status: Insufficient Gas.").is_none());
+        assert!(MoveAbort::parse("Move Runtime Abort. Location: garbage").is_none());
+        assert!(MoveAbort::parse("").is_none());
+    }
+
+    #[test]
+    fn splits_clever_error_codes() {
+        assert_eq!(clever_error_parts(clever_code(27, 3, 5)), (27, 3, 5));
+        assert_eq!(clever_error_parts(clever_code(1, NO_CONSTANT, NO_CONSTANT)), (1, NO_CONSTANT, NO_CONSTANT));
+        // The reserved bits are not part of the line
+        assert_eq!(clever_error_parts(clever_code(27, 3, 5) | 0x7fff << 48), (27, 3, 5));
+    }
+
+    #[test]
+    fn resolves_clever_errors() {
+        let mut module = empty_module();
+        module.identifiers.push(Identifier::new("ENotSolved").unwrap());
+        let name_index = (module.identifiers.len() - 1) as u16;
+        module.constant_pool.push(Constant {
+            type_: SignatureToken::U64,
+            data: bcs::to_bytes(&7u64).unwrap(),
+        });
+        module.constant_pool.push(Constant {
+            type_: SignatureToken::Vector(Box::new(SignatureToken::U8)),
+            data: bcs::to_bytes(&b"not solved yet".to_vec()).unwrap(),
+        });
+
+        let mut abort = MoveAbort::parse(DISPLAY).unwrap();
+        abort.code = clever_code(12, name_index, 1);
+        abort.resolve(&module, None, &HashMap::new());
+        assert_eq!(abort.constant.as_deref(), Some("ENotSolved"));
+        assert_eq!(abort.message.as_deref(), Some("not solved yet"));
+        assert!(abort.describe_code().ends_with("(ENotSolved: not solved yet)"));
+
+        // Non-string constants have no message, missing indices give nothing
+        abort.code = clever_code(12, name_index, 0);
+        abort.constant = None;
+        abort.message = None;
+        abort.resolve(&module, None, &HashMap::new());
+        assert_eq!(abort.constant.as_deref(), Some("ENotSolved"));
+        assert_eq!(abort.message, None);
+
+        abort.code = clever_code(12, NO_CONSTANT, NO_CONSTANT);
+        abort.constant = None;
+        abort.resolve(&module, None, &HashMap::new());
+        assert_eq!((abort.constant, abort.message), (None, None));
+    }
+}
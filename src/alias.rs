@@ -0,0 +1,170 @@
+use sui_transactional_test_runner::test_adapter::FakeID;
+
+use crate::{output, SuiTF};
+
+// Names for objects, so that authors and players can write `"pool"` instead of
+// `FakeID::Enumerated(2, 1)`. Aliases are set with `SuiTF::alias`, or bound by an
+// `AliasRule` when a matching object is created. Wherever an object is taken, by the
+// `SuiTF` API (`impl Into<ObjectKey>`) or as a JSON-lines argument (`{"object": "pool"}`),
+// an alias works as well as an ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectKey {
+    Id(FakeID),
+    Alias(String),
+}
+
+impl From<FakeID> for ObjectKey {
+    fn from(id: FakeID) -> Self {
+        ObjectKey::Id(id)
+    }
+}
+
+impl From<&str> for ObjectKey {
+    fn from(alias: &str) -> Self {
+        ObjectKey::Alias(alias.to_string())
+    }
+}
+
+impl From<String> for ObjectKey {
+    fn from(alias: String) -> Self {
+        ObjectKey::Alias(alias)
+    }
+}
+
+// Names the `nth` object (from 1) of type `object_type` created, by task `task` only
+// when given, e.g. "the first `otterswap::Pool` created by task 3":
+//
+//   suitf.auto_alias(AliasRule::new("pool", "otterswap::Pool").task(3));
+#[derive(Debug, Clone)]
+pub struct AliasRule {
+    pub name: String,
+    // `module::Name` or `package::module::Name`, type arguments are ignored unless given
+    pub object_type: String,
+    pub task: Option<u64>,
+    pub nth: usize,
+    // Matching objects created so far
+    pub seen: usize,
+}
+
+impl AliasRule {
+    pub fn new(
+        name: &str,
+        object_type: &str
+    ) -> Self {
+        AliasRule {
+            name: name.to_string(),
+            object_type: object_type.to_string(),
+            task: None,
+            nth: 1,
+            seen: 0,
+        }
+    }
+
+    pub fn task(
+        mut self,
+        task: u64
+    ) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    pub fn nth(
+        mut self,
+        nth: usize
+    ) -> Self {
+        self.nth = nth.max(1);
+        self
+    }
+
+    // Count a created object, true when it is the one to name
+    pub fn observe(
+        &mut self,
+        suitf: &SuiTF,
+        task: u64,
+        object_type: &str
+    ) -> bool {
+        if self.task.map_or(false, |t| t != task) || !type_matches(suitf, object_type, &self.object_type) {
+            return false;
+        }
+        self.seen += 1;
+        self.seen == self.nth
+    }
+}
+
+// Aliases are names, not numbers or hex IDs, so they never shadow an ID
+pub fn is_valid_alias(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+// `module::Name` is authoring shorthand for that type in any package, other types
+// are compared in full
+fn type_matches(
+    suitf: &SuiTF,
+    actual: &str,
+    expected: &str
+) -> bool {
+    let actual = if expected.contains('<') {
+        actual
+    } else {
+        actual.split('<').next().unwrap_or(actual)
+    };
+    if expected.matches("::").count() == 1 {
+        actual.ends_with(&format!("::{}", expected)) || actual == expected
+    } else {
+        output::type_matches(suitf, actual, expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transcript, worker};
+
+    // Task number of the coin created by funding `solver`
+    async fn fund_solver(suitf: &mut SuiTF) -> u64 {
+        let sender = suitf.get_account_address("solver").unwrap();
+        suitf.fund_account("solver".to_string(), 1000, sender).await.unwrap();
+        suitf
+            .get_known_objects()
+            .into_iter()
+            .filter_map(|id| match id {
+                FakeID::Enumerated(task, _) => Some(task),
+                FakeID::Known(_) => None,
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn validates_aliases() {
+        assert!(is_valid_alias("pool"));
+        assert!(is_valid_alias("_admin-cap.2"));
+        assert!(!is_valid_alias(""));
+        assert!(!is_valid_alias("2pool"));
+        assert!(!is_valid_alias("0x2"));
+        assert!(!is_valid_alias("my pool"));
+    }
+
+    #[test]
+    fn lookups_take_task_numbers() {
+        worker::block_on_local(async {
+            let mut suitf = SuiTF::initialize(Vec::new(), Some(vec!["solver".to_string()])).await.unwrap();
+            let first = fund_solver(&mut suitf).await;
+            assert_eq!(fund_solver(&mut suitf).await, first + 1);
+
+            // A rule still waiting for its match looks up every object created, one task each
+            suitf.auto_alias(AliasRule::new("tenth_coin", "coin::Coin").nth(10)).unwrap();
+            let third = fund_solver(&mut suitf).await;
+            assert_eq!(third, first + 2);
+            assert_eq!(fund_solver(&mut suitf).await, third + 2);
+
+            // Lookups are recorded, so a replay without the rule numbers objects the same
+            let (replayed, report) = transcript::rebuild(suitf.get_history()).await.unwrap();
+            assert!(report.is_identical(), "{:?}", report.divergences);
+            assert_eq!(replayed.get_known_objects().len(), suitf.get_known_objects().len());
+        })
+        .unwrap();
+    }
+}
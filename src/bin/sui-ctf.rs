@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+
+use sui_ctf_framework::{
+    harness::ChallengeTest,
+    logging,
+    manifest::{self, CallSpec, Manifest},
+    transcript::{self, Outcome},
+    worker,
+};
+
+#[derive(Parser)]
+#[clap(name = "sui-ctf", version, about = "Serve and test Sui CTF challenges")]
+struct Cli {
+    #[clap(long, global = true, help = "Log errors only")]
+    quiet: bool,
+    #[clap(long, global = true, help = "Log filter, e.g. `debug` or `warn,sui_ctf_framework=debug` (overridden by SUI_CTF_LOG)")]
+    log: Option<String>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[clap(about = "Serve a challenge from a challenge.toml, or from a built package with --check")]
+    Serve {
+        #[clap(parse(from_os_str), help = "challenge.toml, a directory containing one, or a build/<package> directory")]
+        path: PathBuf,
+        #[clap(long, help = "Win condition call for a built package, `module::function` run as the solver")]
+        check: Option<String>,
+    },
+    #[clap(about = "Run a built solution package against a challenge in-process")]
+    SolveLocal {
+        #[clap(parse(from_os_str))]
+        manifest: PathBuf,
+        #[clap(parse(from_os_str), help = "Move package or build/<package> directory of the solution")]
+        solution: PathBuf,
+    },
+    #[clap(about = "Check that the reference solution wins and that an empty solution does not")]
+    Check {
+        #[clap(parse(from_os_str))]
+        manifest: PathBuf,
+        #[clap(parse(from_os_str), help = "Move package or build/<package> directory of the reference solution")]
+        solution: PathBuf,
+    },
+    #[clap(about = "Re-execute a recorded session transcript")]
+    Replay {
+        #[clap(parse(from_os_str))]
+        transcript: PathBuf,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    if cli.quiet {
+        logging::init_quiet()?;
+    } else {
+        logging::init_with(cli.log.as_deref().unwrap_or(logging::DEFAULT_FILTER))?;
+    }
+
+    // Sessions run on threads of their own, the other commands drive the adapter here,
+    // which spawns local tasks (see `worker`)
+    match cli.command {
+        Command::Serve { path, check } => serve(&path, check.as_deref()),
+        Command::SolveLocal { manifest, solution } => worker::block_on_local(solve_local(&manifest, &solution))?,
+        Command::Check { manifest, solution } => worker::block_on_local(check(&manifest, &solution))?,
+        Command::Replay { transcript } => worker::block_on_local(replay(&transcript))?,
+    }
+}
+
+fn serve(
+    path: &Path,
+    check: Option<&str>
+) -> Result<(), Box<dyn Error>> {
+    let mut manifest = load_manifest(path)?;
+    if let Some(check) = check {
+        manifest.check = Some(parse_check(check)?);
+    }
+    if manifest.check.is_none() && manifest.win.is_none() {
+        return Err("The challenge has no win condition, add one to the manifest or pass --check".into());
+    }
+    tracing::info!(challenge = %manifest.challenge.name, path = %path.display(), "loaded challenge");
+    manifest::serve(manifest)
+}
+
+async fn solve_local(
+    manifest: &Path,
+    solution: &Path
+) -> Result<(), Box<dyn Error>> {
+    let test = ChallengeTest::from_manifest(load_manifest(manifest)?).solution(solution);
+    match test.run().await?.result {
+        Ok(()) => {
+            println!("[*] Solved!");
+            Ok(())
+        }
+        Err(reason) => Err(format!("Not solved: {}", reason).into()),
+    }
+}
+
+async fn check(
+    manifest: &Path,
+    solution: &Path
+) -> Result<(), Box<dyn Error>> {
+    let test = ChallengeTest::from_manifest(load_manifest(manifest)?).solution(solution);
+
+    if test.run_without_solution().await?.is_solved() {
+        return Err("The challenge is solved without any solution".into());
+    }
+    println!("[*] Empty solution fails");
+
+    test.run().await?.result
+        .map_err(|reason| format!("The reference solution does not solve the challenge: {}", reason))?;
+    println!("[*] Reference solution solves the challenge");
+    Ok(())
+}
+
+async fn replay(transcript: &Path) -> Result<(), Box<dyn Error>> {
+    let report = transcript::replay(transcript).await?;
+    for divergence in report.divergences.iter() {
+        println!(
+            "[!] #{} {}: expected {}, got {}",
+            divergence.seq,
+            divergence.operation,
+            describe(&divergence.expected),
+            describe(&divergence.actual)
+        );
+    }
+    if !report.is_identical() {
+        return Err(format!("{} of {} operations diverged", report.divergences.len(), report.operations).into());
+    }
+    println!("[*] {} operations replayed identically", report.operations);
+    Ok(())
+}
+
+// A `challenge.toml`, a directory containing one, or a bare build output
+fn load_manifest(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+    if path.is_file() {
+        return Manifest::load(path);
+    }
+    let manifest_path = path.join("challenge.toml");
+    if manifest_path.is_file() {
+        return Manifest::load(&manifest_path);
+    }
+    Manifest::from_build_dir(path)
+}
+
+// `module::function` of the challenge package, or `package::module::function`
+fn parse_check(text: &str) -> Result<CallSpec, Box<dyn Error>> {
+    let parts: Vec<&str> = text.split("::").collect();
+    let (package, module, function) = match parts.as_slice() {
+        [module, function] => ("challenge", *module, *function),
+        [package, module, function] => (*package, *module, *function),
+        _ => return Err(format!("Bad check '{}', expected module::function", text).into()),
+    };
+
+    Ok(CallSpec::new(package, function).module(module))
+}
+
+fn describe(outcome: &Outcome) -> String {
+    match &outcome.error {
+        Some(error) => format!("error '{}'", error),
+        None => format!("success with output {}", &outcome.output_hash[..16.min(outcome.output_hash.len())]),
+    }
+}
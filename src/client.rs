@@ -0,0 +1,358 @@
+use std::error::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use tracing::info;
+
+pub use sui_transactional_test_runner::test_adapter::FakeID;
+
+use crate::{jsonl, package, pow, protocol};
+
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Solver side of a challenge connection: framed uploads, plain text output,
+// and the JSON-lines commands of `jsonl`
+pub struct Client {
+    stream: jsonl::BufStream<TcpStream>,
+    next_id: u64,
+}
+
+// Turn socket timeouts into a readable error
+fn io_error(
+    err: std::io::Error,
+    action: &str
+) -> Box<dyn Error> {
+    match err.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => format!("Timed out {}", action).into(),
+        _ => format!("Failed {}: {}", action, err).into(),
+    }
+}
+
+// Split `<build_dir>/<package>` into the build directory and the package name
+fn split_package_path(path: &Path) -> Result<(&Path, &str), Box<dyn Error>> {
+    let package_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Bad package path {}", path.display()))?;
+    let build_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    Ok((build_dir, package_name))
+}
+
+// JSON argument for an object, see `jsonl::parse_value`
+pub fn object_arg(id: FakeID) -> Value {
+    match id {
+        FakeID::Enumerated(task, idx) => json!({ "object": [task, idx] }),
+        FakeID::Known(id) => json!({ "object": id.to_string() }),
+    }
+}
+
+// JSON argument of an explicit type, e.g. `typed_arg("u8", json!(1))`
+pub fn typed_arg(
+    type_: &str,
+    value: Value
+) -> Value {
+    json!({ type_: value })
+}
+
+impl Client {
+    pub fn connect(
+        host: &str,
+        port: u16
+    ) -> Result<Client, Box<dyn Error>> {
+        let addrs: Vec<_> = (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}:{}: {}", host, port, e))?
+            .collect();
+
+        let mut last_err = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, DEFAULT_CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(DEFAULT_IO_TIMEOUT))?;
+                    stream.set_write_timeout(Some(DEFAULT_IO_TIMEOUT))?;
+                    info!(address = %addr, "connected");
+                    return Ok(Client { stream: jsonl::BufStream::new(stream), next_id: 1 });
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        match last_err {
+            Some(e) => Err(io_error(e, &format!("connecting to {}:{}", host, port))),
+            None => Err(format!("No address found for {}:{}", host, port).into()),
+        }
+    }
+
+    pub fn set_timeout(
+        &mut self,
+        timeout: Duration
+    ) -> Result<(), Box<dyn Error>> {
+        self.stream.get_ref().set_read_timeout(Some(timeout))?;
+        self.stream.get_ref().set_write_timeout(Some(timeout))?;
+        Ok(())
+    }
+
+    // Server output must be read through the returned stream, which may hold buffered bytes
+    pub fn stream(&mut self) -> &mut jsonl::BufStream<TcpStream> {
+        &mut self.stream
+    }
+
+    pub fn send_line(
+        &mut self,
+        line: &str
+    ) -> Result<(), Box<dyn Error>> {
+        self.stream
+            .write_all(format!("{}\n", line).as_bytes())
+            .and_then(|_| self.stream.flush())
+            .map_err(|e| io_error(e, "sending to the server"))
+    }
+
+    // Read the next non-empty line of server output
+    pub fn read_line(&mut self) -> Result<String, Box<dyn Error>> {
+        match jsonl::read_line(&mut self.stream, jsonl::MAX_LINE_LEN) {
+            Ok(Some(line)) => Ok(line),
+            Ok(None) => Err("Server closed the connection".into()),
+            Err(e) => match e.downcast::<std::io::Error>() {
+                Ok(e) => Err(io_error(*e, "reading from the server")),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    // Read server output up to and including `marker`, e.g. a menu prompt
+    pub fn read_until(
+        &mut self,
+        marker: &str
+    ) -> Result<String, Box<dyn Error>> {
+        let mut data = Vec::new();
+        let mut byte = [0u8; 1];
+        while !data.ends_with(marker.as_bytes()) {
+            match self.stream.read(&mut byte) {
+                Ok(0) => {
+                    return Err(format!(
+                        "Server closed the connection before '{}', got: {}",
+                        marker,
+                        String::from_utf8_lossy(&data)
+                    ).into());
+                }
+                Ok(_) => data.push(byte[0]),
+                Err(e) => return Err(io_error(e, &format!("waiting for '{}'", marker))),
+            }
+        }
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+
+    // Read everything the server sends until it closes the connection
+    pub fn read_to_end(&mut self) -> Result<String, Box<dyn Error>> {
+        let mut data = Vec::new();
+        self.stream
+            .read_to_end(&mut data)
+            .map_err(|e| io_error(e, "reading from the server"))?;
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+
+    // Answer the server's team token prompt, for per-team flags
+    pub fn send_team_token(
+        &mut self,
+        token: &str
+    ) -> Result<(), Box<dyn Error>> {
+        self.read_until("[SERVER] Team token: ")?;
+        self.send_line(token)
+    }
+
+    // Answer the server's proof of work, see `pow`
+    pub fn solve_pow(&mut self) -> Result<(), Box<dyn Error>> {
+        let (prefix, difficulty) = loop {
+            let line = self.read_line()?;
+            if let Some(challenge) = pow::parse_challenge_line(&line) {
+                break challenge;
+            }
+        };
+
+        info!(difficulty, "solving proof of work");
+        let solution = pow::solve(&prefix, difficulty);
+        self.read_until("[POW] Solution: ")?;
+        self.send_line(&solution)?;
+
+        let reply = self.read_line()?;
+        if reply != "[POW] Accepted" {
+            return Err(format!("Proof of work rejected: {}", reply).into());
+        }
+        Ok(())
+    }
+
+    // Send a built package (`<build_dir>/<package>`) as a framed upload, see `protocol`
+    pub fn upload_package(
+        &mut self,
+        path: &Path
+    ) -> Result<(), Box<dyn Error>> {
+        let (build_dir, package_name) = split_package_path(path)?;
+        let package = protocol::PackageUpload {
+            named_address: None,
+            dependencies: Vec::new(),
+            modules: package::load_package_uploads(build_dir, package_name)?,
+        };
+        self.upload(&package)
+    }
+
+    pub fn upload(
+        &mut self,
+        package: &protocol::PackageUpload
+    ) -> Result<(), Box<dyn Error>> {
+        protocol::write_package(&mut self.stream, package, true)?;
+        info!(modules = package.modules.len(), "uploaded package");
+        Ok(())
+    }
+
+    // Send a JSON-lines command and wait for its response
+    pub fn request(
+        &mut self,
+        cmd: &str,
+        fields: Value
+    ) -> Result<Value, Box<dyn Error>> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut request = match fields {
+            Value::Object(map) => map,
+            Value::Null => serde_json::Map::new(),
+            other => return Err(format!("Command fields must be a JSON object, got {}", other).into()),
+        };
+        request.insert("id".to_string(), json!(id));
+        request.insert("cmd".to_string(), json!(cmd));
+        self.send_line(&Value::Object(request).to_string())?;
+
+        // Plain text the server printed before answering, e.g. setup announcements
+        let line = loop {
+            let line = self.read_line()?;
+            if line.starts_with('{') {
+                break line;
+            }
+            info!(line = %line, "server output");
+        };
+        let response: Value = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid response to '{}': {} ({})", cmd, e, line))?;
+        if response["id"] != json!(id) {
+            return Err(format!("Response id {} does not match request id {}", response["id"], id).into());
+        }
+
+        if response["ok"].as_bool() == Some(true) {
+            Ok(response["result"].clone())
+        } else {
+            let error = response["error"].as_str().unwrap_or("unknown error");
+            Err(format!("'{}' failed: {}", cmd, error).into())
+        }
+    }
+
+    // Publish a built package (`<build_dir>/<package>`) through the `publish` command
+    pub fn publish(
+        &mut self,
+        path: &Path,
+        named_address: Option<&str>,
+        dependencies: &[&str]
+    ) -> Result<Value, Box<dyn Error>> {
+        let (build_dir, package_name) = split_package_path(path)?;
+        let modules: Vec<Value> = package::load_package_uploads(build_dir, package_name)?
+            .into_iter()
+            .map(|m| json!({ "name": m.name, "bytes": BASE64.encode(m.bytes) }))
+            .collect();
+
+        self.request("publish", json!({
+            "modules": modules,
+            "named_address": named_address,
+            "dependencies": dependencies,
+        }))
+    }
+
+    pub fn call(
+        &mut self,
+        package: &str,
+        module: Option<&str>,
+        function: &str,
+        args: Vec<Value>,
+        type_args: &[&str]
+    ) -> Result<Value, Box<dyn Error>> {
+        self.request("call", json!({
+            "package": package,
+            "module": module,
+            "function": function,
+            "args": args,
+            "type_args": type_args,
+        }))
+    }
+
+    pub fn view(
+        &mut self,
+        id: FakeID
+    ) -> Result<Value, Box<dyn Error>> {
+        let id = match id {
+            FakeID::Enumerated(task, idx) => json!([task, idx]),
+            FakeID::Known(id) => json!(id.to_string()),
+        };
+        self.request("view", json!({ "id": id }))
+    }
+
+    // Dynamic fields of an object, or of its collection `field`, e.g. `Some("balances")`
+    pub fn dynamic_fields(
+        &mut self,
+        id: Value,
+        field: Option<&str>,
+        key: Option<Value>
+    ) -> Result<Value, Box<dyn Error>> {
+        let mut fields = json!({ "id": id, "field": field });
+        if let Some(key) = key {
+            fields["key"] = key;
+        }
+        self.request("dynamic_fields", fields)
+    }
+
+    // Named and known objects of the session
+    pub fn objects(&mut self) -> Result<Value, Box<dyn Error>> {
+        self.request("objects", json!({}))
+    }
+
+    pub fn ptb(
+        &mut self,
+        inputs: Vec<Value>,
+        commands: &[&str]
+    ) -> Result<Value, Box<dyn Error>> {
+        self.request("ptb", json!({ "inputs": inputs, "commands": commands }))
+    }
+
+    pub fn events(
+        &mut self,
+        since: usize
+    ) -> Result<Value, Box<dyn Error>> {
+        self.request("events", json!({ "since": since }))
+    }
+
+    pub fn balance(
+        &mut self,
+        owner: Option<&str>,
+        coin_type: Option<&str>
+    ) -> Result<u128, Box<dyn Error>> {
+        let result = self.request("balance", json!({ "owner": owner, "coin_type": coin_type }))?;
+        result["balance"]
+            .as_str()
+            .and_then(|b| b.parse().ok())
+            .ok_or_else(|| format!("Bad balance in response: {}", result).into())
+    }
+
+    pub fn get_flag(&mut self) -> Result<String, Box<dyn Error>> {
+        let result = self.request("get_flag", Value::Null)?;
+        result["flag"]
+            .as_str()
+            .map(|flag| flag.to_string())
+            .ok_or_else(|| format!("No flag in response: {}", result).into())
+    }
+
+    pub fn exit(&mut self) -> Result<(), Box<dyn Error>> {
+        self.request("exit", Value::Null)?;
+        Ok(())
+    }
+}
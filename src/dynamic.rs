@@ -0,0 +1,370 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+use sui_transactional_test_runner::test_adapter::FakeID;
+use sui_types::base_types::ObjectID;
+
+use move_core_types::account_address::AccountAddress;
+
+use crate::{alias::ObjectKey, jsonl, output, rpc, win, SuiTF};
+
+// Dynamic fields, and with them the entries of `Table`, `Bag`, `ObjectTable`, `ObjectBag`
+// and `LinkedTable`, are `0x2::dynamic_field::Field<K, V>` objects owned by the UID of
+// their parent: the object itself for raw dynamic fields, or the collection's own UID
+// inside it. They are found among the objects the session has seen created, and their
+// names and values typed by the layouts of `K` and `V` (see `win::decode_contents`).
+//
+//   let entries = suitf.dynamic_fields("pool", Some("balances")).await?;
+//   let entry = suitf.dynamic_field("pool", Some("balances"), &json!("solver")).await?;
+//
+// Fields of dynamic object fields (`ObjectTable`, `dynamic_object_field`) name a child
+// object, whose contents are given as the value.
+const FIELD_TYPE: &str = "0x2::dynamic_field::Field<";
+const WRAPPER_TYPE: &str = "0x2::dynamic_object_field::Wrapper<";
+
+#[derive(Debug, Clone)]
+pub struct DynamicField {
+    // The `Field` object holding the entry
+    pub id: FakeID,
+    pub name_type: String,
+    pub name: Value,
+    pub value_type: String,
+    pub value: Value,
+    // Child object of a dynamic object field
+    pub object: Option<FakeID>,
+}
+
+impl DynamicField {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": jsonl::fake_json(self.id),
+            "name_type": self.name_type,
+            "name": self.name,
+            "value_type": self.value_type,
+            "value": self.value,
+            "object": self.object.map(jsonl::fake_json),
+        })
+    }
+
+    // Whether the field is named `key`, in the JSON-lines form of the name type:
+    // `5`, `"solver"` or `"0x.."` for addresses, `{"a": 1}` for structs
+    pub fn has_key(
+        &self,
+        suitf: &SuiTF,
+        key: &Value
+    ) -> bool {
+        win::decode_contents(suitf, key, &self.name_type) == self.name
+    }
+}
+
+// Every dynamic field of `parent`, or of its collection `field` (`balances`, nested
+// fields separated by dots) when given
+pub async fn list(
+    suitf: &mut SuiTF,
+    parent: impl Into<ObjectKey>,
+    field: Option<&str>
+) -> Result<Vec<DynamicField>, Box<dyn Error>> {
+    let parent = suitf.resolve_object(parent)?;
+    let raw = suitf
+        .lookup_object(parent)
+        .await?
+        .filter(|raw| output::parse_object_type(raw).is_some())
+        .ok_or_else(|| format!("Object {} not found", jsonl::fake_json(parent)))?;
+    let uid = parent_uid(suitf, parent, &raw, field)?;
+
+    let mut fields = Vec::new();
+    for (task, idx) in suitf.known_objects.clone() {
+        let id = FakeID::Enumerated(task, idx);
+        if id == parent {
+            continue;
+        }
+
+        // Whether an object is a field and of which UID never changes, so each object is
+        // looked up once to find out, then only the fields of `uid` are looked up again
+        let cached = suitf.field_parents.get(&(task, idx)).cloned();
+        let (owner_uid, raw) = match cached {
+            Some(owner_uid) => (owner_uid, None),
+            None => {
+                let raw = suitf.lookup_object(id).await.ok().flatten();
+                let owner_uid = raw.as_deref().and_then(|raw| field_owner_uid(suitf, raw));
+                suitf.field_parents.insert((task, idx), owner_uid.clone());
+                (owner_uid, raw)
+            }
+        };
+        if owner_uid.as_deref() != Some(uid.as_str()) {
+            continue;
+        }
+        let raw = match raw {
+            Some(raw) => Some(raw),
+            None => suitf.lookup_object(id).await.ok().flatten(),
+        };
+        let mut entry = match raw.and_then(|raw| parse_field(suitf, id, &raw)) {
+            Some(entry) => entry,
+            None => {
+                // A field that can no longer be viewed was removed for good
+                suitf.field_parents.insert((task, idx), None);
+                continue;
+            }
+        };
+
+        if let Some(child) = entry.object {
+            if let Ok(Some(child_raw)) = suitf.lookup_object(child).await {
+                if let Some(child_type) = output::parse_object_type(&child_raw) {
+                    let child_type = jsonl::resolve_type_names(suitf, &child_type);
+                    let contents = SuiTF::parse_output(&child_raw).get("Contents").cloned().unwrap_or(Value::Null);
+                    entry.value = win::decode_contents(suitf, &contents, &child_type);
+                    entry.value_type = child_type;
+                }
+            }
+        }
+        fields.push(entry);
+    }
+    Ok(fields)
+}
+
+// The dynamic field of `parent` (or of its collection `field`) named `key`
+pub async fn get(
+    suitf: &mut SuiTF,
+    parent: impl Into<ObjectKey>,
+    field: Option<&str>,
+    key: &Value
+) -> Result<Option<DynamicField>, Box<dyn Error>> {
+    let fields = list(suitf, parent, field).await?;
+    Ok(fields.into_iter().find(|entry| entry.has_key(suitf, key)))
+}
+
+// Key and value types of a `0x2::dynamic_field::Field<K, V>`, `None` for other types
+pub fn field_types(type_: &str) -> Option<(String, String)> {
+    let args = type_.strip_prefix(FIELD_TYPE)?.strip_suffix('>')?;
+    let mut depth = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => return Some((args[..i].trim().to_string(), args[i + 1..].trim().to_string())),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Decode a viewed `Field` object, the value of an object field is its child's ID until
+// the child is viewed
+fn parse_field(
+    suitf: &SuiTF,
+    id: FakeID,
+    raw: &str
+) -> Option<DynamicField> {
+    let type_ = jsonl::resolve_type_names(suitf, &output::parse_object_type(raw)?);
+    let (name_type, value_type) = field_types(&type_)?;
+    let contents = SuiTF::parse_output(raw).get("Contents").cloned().unwrap_or(Value::Null);
+    let name = contents.get("name").unwrap_or(&Value::Null);
+    let value = win::decode_contents(suitf, contents.get("value").unwrap_or(&Value::Null), &value_type);
+
+    match name_type.strip_prefix(WRAPPER_TYPE).and_then(|t| t.strip_suffix('>')) {
+        Some(inner) => {
+            let name = win::decode_contents(suitf, name.get("name").unwrap_or(name), inner);
+            let object = value
+                .as_str()
+                .and_then(|child| ObjectID::from_hex_literal(child).ok())
+                .map(rpc::object_id_to_fake);
+            Some(DynamicField { id, name_type: inner.to_string(), name, value_type, value, object })
+        }
+        None => Some(DynamicField {
+            id,
+            name: win::decode_contents(suitf, name, &name_type),
+            name_type,
+            value_type,
+            value,
+            object: None,
+        }),
+    }
+}
+
+// UID the fields hang from, as a hex literal
+fn parent_uid(
+    suitf: &SuiTF,
+    parent: FakeID,
+    raw: &str,
+    field: Option<&str>
+) -> Result<String, Box<dyn Error>> {
+    let field = match field {
+        Some(field) => field,
+        None => {
+            let id = match parent {
+                FakeID::Enumerated(task, idx) => rpc::fake_to_object_id(task, idx),
+                FakeID::Known(id) => id,
+            };
+            return Ok(AccountAddress::new(id.into_bytes()).to_hex_literal());
+        }
+    };
+
+    let pointer = format!("/Contents/{}", field.replace('.', "/"));
+    let contents = SuiTF::parse_output(raw);
+    let collection = contents
+        .pointer(&pointer)
+        .ok_or_else(|| format!("Object {} has no field '{}'", jsonl::fake_json(parent), field))?;
+    // Collections start with their `id: UID`, a field may also be the UID itself
+    let uid = collection.get("id").unwrap_or(collection);
+    match win::decode_contents(suitf, uid, "0x2::object::UID") {
+        Value::String(uid) => Ok(uid),
+        _ => Err(format!("Field '{}' of object {} has no UID", field, jsonl::fake_json(parent)).into()),
+    }
+}
+
+// UID a viewed object hangs from when it is a `Field`. Objects that cannot be viewed are
+// deleted or wrapped, and fields cannot be wrapped, so neither can become a field later.
+fn field_owner_uid(
+    suitf: &SuiTF,
+    raw: &str
+) -> Option<String> {
+    let type_ = jsonl::resolve_type_names(suitf, &output::parse_object_type(raw)?);
+    field_types(&type_)?;
+    owner_id(suitf, &output::parse_object_owner(raw)?)
+}
+
+// `Object ID: ( fake(2,1) )` owners, as a hex literal
+fn owner_id(
+    suitf: &SuiTF,
+    owner: &str
+) -> Option<String> {
+    let id = owner.strip_prefix("Object ID:")?.trim().trim_start_matches('(').trim_end_matches(')');
+    Some(win::address_text(suitf, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::with_session;
+
+    // Views as the adapter prints them: a `u64 => u64` entry of fake(2,1)'s table, an
+    // object field of fake(2,0) holding fake(3,1), and the pool with the table
+    const FIELD: &str = "\
+Owner: Object ID: ( fake(2,1) )
+Version: 3
+Contents: sui::dynamic_field::Field<u64, u64> {
+    id: sui::object::UID {
+        id: sui::object::ID {
+            bytes: fake(3,0),
+        },
+    },
+    name: 5u64,
+    value: 10u64,
+}";
+
+    const OBJECT_FIELD: &str = "\
+Owner: Object ID: ( fake(2,0) )
+Version: 3
+Contents: sui::dynamic_field::Field<sui::dynamic_object_field::Wrapper<u64>, sui::object::ID> {
+    id: sui::object::UID {
+        id: sui::object::ID {
+            bytes: fake(3,2),
+        },
+    },
+    name: sui::dynamic_object_field::Wrapper<u64> {
+        name: 7u64,
+    },
+    value: sui::object::ID {
+        bytes: fake(3,1),
+    },
+}";
+
+    const POOL: &str = "\
+Owner: Shared( 2 )
+Version: 2
+Contents: challenge::pool::Pool {
+    id: sui::object::UID {
+        id: sui::object::ID {
+            bytes: fake(2,0),
+        },
+    },
+    ledger: challenge::pool::Ledger {
+        balances: sui::table::Table<address, u64> {
+            id: sui::object::UID {
+                id: sui::object::ID {
+                    bytes: fake(2,1),
+                },
+            },
+            size: 1u64,
+        },
+    },
+    fee: 30u64,
+}";
+
+    fn fake_hex(
+        task: u64,
+        idx: u64
+    ) -> String {
+        AccountAddress::new(rpc::fake_to_object_id(task, idx).into_bytes()).to_hex_literal()
+    }
+
+    #[test]
+    fn splits_field_types() {
+        assert_eq!(
+            field_types("0x2::dynamic_field::Field<u64, u64>"),
+            Some(("u64".to_string(), "u64".to_string()))
+        );
+        assert_eq!(
+            field_types("0x2::dynamic_field::Field<0x1::string::String, 0x2::balance::Balance<0x2::sui::SUI>>"),
+            Some(("0x1::string::String".to_string(), "0x2::balance::Balance<0x2::sui::SUI>".to_string()))
+        );
+        assert_eq!(
+            field_types("0x2::dynamic_field::Field<0x2::dynamic_object_field::Wrapper<0x5::m::Key<u8, u16>>, 0x2::object::ID>"),
+            Some(("0x2::dynamic_object_field::Wrapper<0x5::m::Key<u8, u16>>".to_string(), "0x2::object::ID".to_string()))
+        );
+
+        // Other types and names the adapter prints are not fields
+        assert_eq!(field_types("0x2::coin::Coin<0x2::sui::SUI>"), None);
+        assert_eq!(field_types("sui::dynamic_field::Field<u64, u64>"), None);
+        assert_eq!(field_types("0x2::dynamic_field::Field<u64>"), None);
+    }
+
+    #[test]
+    fn parses_fields() {
+        with_session(|suitf| {
+            let entry = parse_field(suitf, FakeID::Enumerated(3, 0), FIELD).unwrap();
+            assert_eq!(entry.id, FakeID::Enumerated(3, 0));
+            assert_eq!((entry.name_type.as_str(), entry.value_type.as_str()), ("u64", "u64"));
+            assert_eq!((entry.name, entry.value), (json!(5), json!(10)));
+            assert_eq!(entry.object, None);
+
+            // Object fields are named by the type inside the wrapper and point at their child
+            let entry = parse_field(suitf, FakeID::Enumerated(3, 2), OBJECT_FIELD).unwrap();
+            assert_eq!(entry.name_type, "u64");
+            assert_eq!(entry.name, json!(7));
+            assert_eq!(entry.value, json!(fake_hex(3, 1)));
+            assert_eq!(entry.object, Some(FakeID::Enumerated(3, 1)));
+            assert!(entry.has_key(suitf, &json!(7)));
+            assert!(!entry.has_key(suitf, &json!(5)));
+
+            assert!(parse_field(suitf, FakeID::Enumerated(2, 0), POOL).is_none());
+        });
+    }
+
+    #[test]
+    fn finds_collection_uids() {
+        with_session(|suitf| {
+            let pool = FakeID::Enumerated(2, 0);
+            // Raw fields hang from the object, collection entries from the collection
+            assert_eq!(parent_uid(suitf, pool, POOL, None).unwrap(), fake_hex(2, 0));
+            assert_eq!(parent_uid(suitf, pool, POOL, Some("ledger.balances")).unwrap(), fake_hex(2, 1));
+            assert_eq!(parent_uid(suitf, pool, POOL, Some("id")).unwrap(), fake_hex(2, 0));
+
+            assert!(parent_uid(suitf, pool, POOL, Some("balances")).is_err());
+        });
+    }
+
+    #[test]
+    fn finds_field_owners() {
+        with_session(|suitf| {
+            assert_eq!(field_owner_uid(suitf, FIELD), Some(fake_hex(2, 1)));
+            assert_eq!(field_owner_uid(suitf, OBJECT_FIELD), Some(fake_hex(2, 0)));
+
+            // Objects owned by objects are not all fields, and fields are only owned by UIDs
+            let child = OBJECT_FIELD.replace("sui::dynamic_field::Field", "challenge::pool::Vault");
+            assert_eq!(field_owner_uid(suitf, &child), None);
+            assert_eq!(field_owner_uid(suitf, POOL), None);
+            assert_eq!(owner_id(suitf, "Account Address ( solver )"), None);
+        });
+    }
+}
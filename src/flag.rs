@@ -0,0 +1,202 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::jsonl;
+
+pub const DEFAULT_FLAG_FORMAT: &str = "flag{{hmac}}";
+pub const MAX_TEAM_TOKEN_LEN: usize = 128;
+
+// Where the flag handed to a winning player comes from
+#[derive(Debug, Clone)]
+pub enum FlagProvider {
+    // Static flag in an environment variable
+    Env(String),
+    // Static flag in a file, read when the flag is handed out
+    File(PathBuf),
+    // Per-team flag: `format` with `{hmac}` replaced by hex(HMAC-SHA256(secret, team token))
+    PerTeam { secret: Vec<u8>, format: String },
+}
+
+impl Default for FlagProvider {
+    fn default() -> Self {
+        FlagProvider::Env("FLAG".to_string())
+    }
+}
+
+impl FlagProvider {
+    // Pick the provider from the environment: `FLAG_SECRET` (and optionally `FLAG_FORMAT`)
+    // for per-team flags, else `FLAG_FILE`, else the `FLAG` variable
+    pub fn from_env() -> Self {
+        if let Ok(secret) = env::var("FLAG_SECRET") {
+            return FlagProvider::PerTeam {
+                secret: secret.into_bytes(),
+                format: env::var("FLAG_FORMAT").unwrap_or_else(|_| DEFAULT_FLAG_FORMAT.to_string()),
+            };
+        }
+        if let Ok(path) = env::var("FLAG_FILE") {
+            return FlagProvider::File(PathBuf::from(path));
+        }
+        FlagProvider::default()
+    }
+
+    pub fn needs_team_token(&self) -> bool {
+        matches!(self, FlagProvider::PerTeam { .. })
+    }
+
+    pub fn flag(
+        &self,
+        team_token: Option<&str>
+    ) -> Result<String, Box<dyn Error>> {
+        match self {
+            FlagProvider::Env(var) => {
+                env::var(var).map_err(|_| format!("Flag variable {} is not set", var).into())
+            }
+            FlagProvider::File(path) => fs::read_to_string(path)
+                .map(|flag| flag.trim().to_string())
+                .map_err(|e| format!("Failed to read flag file {}: {}", path.display(), e).into()),
+            FlagProvider::PerTeam { secret, format } => {
+                let token = team_token.ok_or("No team token for a per-team flag")?;
+                Ok(team_flag(secret, token, format))
+            }
+        }
+    }
+
+    // Whether `flag` is the flag handed to `team_token`
+    pub fn check(
+        &self,
+        flag: &str,
+        team_token: Option<&str>
+    ) -> bool {
+        self.flag(team_token).map(|expected| expected == flag.trim()).unwrap_or(false)
+    }
+
+    // Which of `team_tokens` a submitted flag was issued to. Static flags are shared
+    // by every team, so only per-team flags can be attributed.
+    pub fn find_team<'a>(
+        &self,
+        flag: &str,
+        team_tokens: &[&'a str]
+    ) -> Option<&'a str> {
+        match self {
+            FlagProvider::PerTeam { secret, format } => verify_team_flag(secret, format, flag, team_tokens),
+            _ => None,
+        }
+    }
+}
+
+pub fn team_flag(
+    secret: &[u8],
+    team_token: &str,
+    format: &str
+) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(team_token.as_bytes());
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format.replace("{hmac}", &digest)
+}
+
+pub fn verify_team_flag<'a>(
+    secret: &[u8],
+    format: &str,
+    flag: &str,
+    team_tokens: &[&'a str]
+) -> Option<&'a str> {
+    team_tokens
+        .iter()
+        .find(|token| team_flag(secret, token, format) == flag.trim())
+        .copied()
+}
+
+// Ask the player for their team token
+pub fn read_team_token<S: BufRead + Write>(stream: &mut S) -> Result<String, Box<dyn Error>> {
+    stream.write_all(b"[SERVER] Team token: ")?;
+    stream.flush()?;
+
+    let token = jsonl::read_line(stream, MAX_TEAM_TOKEN_LEN)?
+        .ok_or("Client disconnected before sending a team token")?;
+    if !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Team tokens may only contain letters, digits, '-' and '_'".into());
+    }
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ScriptedStream;
+
+    fn per_team() -> FlagProvider {
+        FlagProvider::PerTeam {
+            secret: b"Jefe".to_vec(),
+            format: DEFAULT_FLAG_FORMAT.to_string(),
+        }
+    }
+
+    #[test]
+    fn team_flags_are_hmacs() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            team_flag(b"Jefe", "what do ya want for nothing?", "{hmac}"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            team_flag(b"Jefe", "what do ya want for nothing?", DEFAULT_FLAG_FORMAT),
+            "flag{5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843}"
+        );
+        assert_eq!(team_flag(b"secret", "team", "CTF{{hmac}}"), team_flag(b"secret", "team", "CTF{{hmac}}"));
+        assert_ne!(team_flag(b"secret", "team-a", "{hmac}"), team_flag(b"secret", "team-b", "{hmac}"));
+        assert_ne!(team_flag(b"secret", "team", "{hmac}"), team_flag(b"other", "team", "{hmac}"));
+    }
+
+    #[test]
+    fn per_team_flags_need_a_token() {
+        let provider = per_team();
+        assert!(provider.needs_team_token());
+        assert!(provider.flag(None).is_err());
+        assert_eq!(provider.flag(Some("team-a")).unwrap(), team_flag(b"Jefe", "team-a", DEFAULT_FLAG_FORMAT));
+        assert!(!FlagProvider::Env("FLAG".to_string()).needs_team_token());
+    }
+
+    #[test]
+    fn checks_and_attributes_flags() {
+        let provider = per_team();
+        let flag = provider.flag(Some("team-a")).unwrap();
+        assert!(provider.check(&flag, Some("team-a")));
+        assert!(provider.check(&format!("  {}\n", flag), Some("team-a")));
+        assert!(!provider.check(&flag, Some("team-b")));
+        assert!(!provider.check(&flag, None));
+
+        assert_eq!(provider.find_team(&flag, &["team-b", "team-a"]), Some("team-a"));
+        assert_eq!(provider.find_team("flag{nope}", &["team-a"]), None);
+        assert_eq!(FlagProvider::Env("FLAG".to_string()).find_team(&flag, &["team-a"]), None);
+    }
+
+    #[test]
+    fn reads_team_tokens() {
+        let mut player = ScriptedStream::new("\nteam_A-1\nnext");
+        assert_eq!(read_team_token(&mut player).unwrap(), "team_A-1");
+        assert_eq!(player.sent, b"[SERVER] Team token: ");
+    }
+
+    #[test]
+    fn rejects_invalid_team_tokens() {
+        for input in ["team a\n", "team;rm\n", "tëam\n"] {
+            assert!(read_team_token(&mut ScriptedStream::new(input)).is_err(), "{:?} accepted", input);
+        }
+
+        let long = format!("{}\n", "a".repeat(MAX_TEAM_TOKEN_LEN + 1));
+        assert!(read_team_token(&mut ScriptedStream::new(&long)).is_err());
+        assert!(read_team_token(&mut ScriptedStream::new("")).unwrap_err().to_string().contains("disconnected"));
+    }
+}
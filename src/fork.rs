@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::sync::Arc;
+
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::FileHash;
+use move_core_types::account_address::AccountAddress;
+use sui_transactional_test_runner::test_adapter::FakeID;
+use tokio::task::JoinHandle;
+
+use crate::{abort, alias, transcript, worker::SuiHandle, SuiTF};
+
+// A set-up session to make independent copies of, for "fork, try, discard" experiments
+// in fuzzers and checkers. The adapter's simulator store cannot be copied through the
+// Sui crates, so a copy re-runs the session's history (see `transcript::rebuild`) in a
+// fresh environment and takes over the bookkeeping a re-run does not give back. A copy
+// costs an initialization plus the template's transactions: `SuiTF::fork` pays it on
+// the spot, `ForkPool` ahead of time.
+//
+// Unlike `SuiTF`, a template is `Send`, so copies can be made on other threads.
+#[derive(Clone)]
+pub struct Template {
+    history: Vec<transcript::Record>,
+    // Replayed publishes have no source maps, and name no objects
+    source_maps: HashMap<(AccountAddress, String), SourceMap>,
+    sources: HashMap<FileHash, abort::SourceFile>,
+    aliases: BTreeMap<String, FakeID>,
+    alias_rules: Vec<alias::AliasRule>,
+}
+
+impl Template {
+    pub fn new(suitf: &SuiTF) -> Self {
+        Template {
+            history: suitf.history.clone(),
+            source_maps: suitf.source_maps.clone(),
+            sources: suitf.sources.clone(),
+            aliases: suitf.aliases.clone(),
+            alias_rules: suitf.alias_rules.clone(),
+        }
+    }
+
+    // Build a copy of the template, which fails when the re-run diverges
+    pub async fn instantiate(&self) -> Result<SuiTF, Box<dyn Error>> {
+        let (mut suitf, report) = transcript::rebuild(&self.history).await?;
+        if let Some(divergence) = report.divergences.first() {
+            return Err(format!("Fork diverged at #{}: {}", divergence.seq, divergence.operation).into());
+        }
+
+        suitf.source_maps.extend(self.source_maps.clone());
+        suitf.sources.extend(self.sources.clone());
+        suitf.aliases = self.aliases.clone();
+        suitf.alias_rules = self.alias_rules.clone();
+        Ok(suitf)
+    }
+}
+
+// Copies of a template built ahead of time, each on a worker thread of its own (see
+// `worker::SuiHandle`). `take` hands out a ready copy and starts another one in its
+// place, so a fuzzer only waits for a copy when it takes them faster than they are built.
+// Must be created and used inside a Tokio runtime.
+pub struct ForkPool {
+    template: Arc<Template>,
+    building: VecDeque<JoinHandle<Result<SuiHandle, String>>>,
+}
+
+impl ForkPool {
+    pub fn new(
+        template: Template,
+        size: usize
+    ) -> Self {
+        let mut pool = ForkPool {
+            template: Arc::new(template),
+            building: VecDeque::new(),
+        };
+        for _ in 0..size.max(1) {
+            pool.build();
+        }
+        pool
+    }
+
+    fn build(&mut self) {
+        let template = self.template.clone();
+        self.building.push_back(tokio::spawn(async move {
+            SuiHandle::spawn(move || async move { template.instantiate().await })
+                .await
+                .map_err(|e| e.to_string())
+        }));
+    }
+
+    // The oldest copy, waiting for it when it is not built yet. Dropping every handle
+    // of a copy discards it.
+    pub async fn take(&mut self) -> Result<SuiHandle, Box<dyn Error>> {
+        self.build();
+        let copy = self.building.pop_front().ok_or("The fork pool is empty")?;
+        Ok(copy.await.map_err(|e| format!("Failed to build a fork: {}", e))??)
+    }
+}
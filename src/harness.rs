@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    manifest::{CallSpec, FundSpec, Manifest, PublishSpec},
+    package,
+    win::WinCondition,
+    SuiTF,
+};
+
+// In-process challenge test: set the challenge up, run the reference solution and
+// check the win condition, without a server or client.
+//
+//   #[tokio::test]
+//   async fn reference_solution_wins() {
+//       ChallengeTest::new("mile-high-city")
+//           .challenge("../chall")
+//           .solution("../solve")
+//           .solution_call(CallSpec::new("solution", "solve").args(vec![json!({ "object": [1, 1] })]))
+//           .check(CallSpec::new("challenge", "check_status").args(vec![json!({ "object": [1, 1] })]))
+//           .assert_solved()
+//           .await;
+//   }
+//
+// Packages are Move package directories with a `Move.toml`, built beforehand with
+// `sui move build`, or `build/<package>` directories.
+pub struct ChallengeTest {
+    manifest: Manifest,
+    solution: Option<PathBuf>,
+}
+
+// Environment after a test run, for further inspection
+pub struct TestRun {
+    pub suitf: SuiTF,
+    // `Ok` when the challenge is solved, otherwise the reason it is not
+    pub result: Result<(), String>,
+}
+
+impl ChallengeTest {
+    pub fn new(name: &str) -> Self {
+        let manifest = Manifest::parse(&format!("[challenge]\nname = {:?}\n", name))
+            .expect("an empty manifest is valid");
+        ChallengeTest::from_manifest(manifest)
+    }
+
+    // Everything but the solution comes from the manifest, see `manifest`
+    pub fn from_manifest(manifest: Manifest) -> Self {
+        ChallengeTest {
+            manifest,
+            solution: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(ChallengeTest::from_manifest(Manifest::load(path)?))
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    // Publish a package under the `challenge` named address
+    pub fn challenge(
+        self,
+        package_dir: impl AsRef<Path>
+    ) -> Self {
+        self.publish(package_dir, "challenge")
+    }
+
+    // Publish a package as `challenger` during setup, in the order of the calls
+    pub fn publish(
+        mut self,
+        package_dir: impl AsRef<Path>,
+        named_address: &str
+    ) -> Self {
+        let output = resolve(package_dir.as_ref());
+        let package = output
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+        self.manifest.publish.push(PublishSpec {
+            build_dir: output.parent().map(Path::to_path_buf).unwrap_or_default(),
+            package,
+            named_address: Some(named_address.to_string()),
+            sender: "challenger".to_string(),
+            dependencies: Vec::new(),
+        });
+        self
+    }
+
+    pub fn fund(
+        mut self,
+        account: &str,
+        amount: u64
+    ) -> Self {
+        self.manifest.fund.push(FundSpec {
+            account: account.to_string(),
+            amount,
+            sender: "challenger".to_string(),
+        });
+        self
+    }
+
+    // Call run after publishing and funding, as `challenger` by default
+    pub fn setup_call(
+        mut self,
+        call: CallSpec
+    ) -> Self {
+        self.manifest.setup.push(call);
+        self
+    }
+
+    // Reference solution, published as `solver` under the `solution` named address
+    pub fn solution(
+        mut self,
+        package_dir: impl AsRef<Path>
+    ) -> Self {
+        self.solution = Some(resolve(package_dir.as_ref()));
+        self
+    }
+
+    // Call run once the solution is published, as `solver` by default
+    pub fn solution_call(
+        mut self,
+        call: CallSpec
+    ) -> Self {
+        self.manifest.solution.call = Some(call);
+        self
+    }
+
+    // Call that must succeed for the challenge to be solved
+    pub fn check(
+        mut self,
+        call: CallSpec
+    ) -> Self {
+        self.manifest.check = Some(call);
+        self
+    }
+
+    pub fn win(
+        mut self,
+        condition: WinCondition
+    ) -> Self {
+        self.manifest.win = Some(condition);
+        self
+    }
+
+    // Run the whole flow with the reference solution. Setup failures are errors,
+    // a failing solution is reported in `TestRun::result`.
+    pub async fn run(&self) -> Result<TestRun, Box<dyn Error>> {
+        let solution = self.solution.as_deref().ok_or("No reference solution, see `ChallengeTest::solution`")?;
+        self.attempt(Some(solution)).await
+    }
+
+    // Run the flow with no solution at all
+    pub async fn run_without_solution(&self) -> Result<TestRun, Box<dyn Error>> {
+        self.attempt(None).await
+    }
+
+    pub async fn assert_solved(&self) -> TestRun {
+        let run = self.run().await.unwrap_or_else(|e| panic!("Challenge setup failed: {}", e));
+        if let Err(reason) = &run.result {
+            panic!("The reference solution does not solve the challenge: {}", reason);
+        }
+        run
+    }
+
+    // The challenge must not be solved without doing anything
+    pub async fn assert_unsolved_without_solution(&self) -> TestRun {
+        let run = self.run_without_solution().await.unwrap_or_else(|e| panic!("Challenge setup failed: {}", e));
+        if run.result.is_ok() {
+            panic!("The challenge is solved without any solution");
+        }
+        run
+    }
+
+    async fn attempt(
+        &self,
+        solution: Option<&Path>
+    ) -> Result<TestRun, Box<dyn Error>> {
+        // The adapter spawns local tasks, see `worker`
+        let local = tokio::task::LocalSet::new();
+        local.run_until(self.attempt_local(solution)).await
+    }
+
+    async fn attempt_local(
+        &self,
+        solution: Option<&Path>
+    ) -> Result<TestRun, Box<dyn Error>> {
+        let mut suitf = self.manifest.initialize().await?;
+        self.manifest.setup(&mut suitf).await?;
+
+        if let Some(solution) = solution {
+            if let Err(e) = self.manifest.run_solution(&mut suitf, solution).await {
+                let result = Err(format!("Solution failed: {}", e));
+                return Ok(TestRun { suitf, result });
+            }
+        }
+
+        let result = self.manifest.check_solved(&mut suitf).await;
+        Ok(TestRun { suitf, result })
+    }
+}
+
+impl TestRun {
+    pub fn is_solved(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+// Panics when a Move package has not been built, a test cannot go further anyway
+fn resolve(dir: &Path) -> PathBuf {
+    package::build_output_dir(dir).unwrap_or_else(|e| panic!("{}", e))
+}
@@ -0,0 +1,896 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use sui_transactional_test_runner::{args::{SuiExtraValueArgs, SuiValue}, test_adapter::FakeID};
+use sui_types::base_types::ObjectID;
+
+use move_compiler::shared::{NumberFormat, NumericalAddress};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::TypeTag,
+    parsing::{address::ParsedAddress, values::ParsedValue},
+    runtime_value::MoveValue,
+    u256::U256,
+};
+
+use crate::{limits::SessionMeter, output, protocol, rpc, server, win, SuiTF};
+
+// JSON-lines command protocol: every request and every response is a single line.
+//
+//   -> {"id": 1, "cmd": "call", "package": "challenge", "function": "solve", "args": [{"object": [1, 0]}]}
+//   <- {"id": 1, "ok": true, "result": {...}}
+//   <- {"id": 1, "ok": false, "error": "..."}
+//
+// Uploaded modules are base64 encoded, so a line may be larger than the raw upload limit
+pub const MAX_LINE_LEN: usize = 2 * protocol::DEFAULT_MAX_UPLOAD_SIZE;
+
+const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+
+// Who the player acts as, and what their packages may depend on
+pub struct CommandConfig {
+    pub player: String,
+    pub default_named_address: String,
+    pub allowed_dependencies: Vec<String>,
+    // Raw size of a `publish` upload, `ServerConfig::max_upload_size` in a session
+    pub max_upload_size: usize,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        CommandConfig {
+            player: "solver".to_string(),
+            default_named_address: "solution".to_string(),
+            allowed_dependencies: vec!["challenge".to_string()],
+            max_upload_size: protocol::DEFAULT_MAX_UPLOAD_SIZE,
+        }
+    }
+}
+
+impl CommandConfig {
+    // Longest request line, room for a base64 upload of `max_upload_size`
+    pub fn max_line_len(&self) -> usize {
+        MAX_LINE_LEN.max(2 * self.max_upload_size)
+    }
+}
+
+// Read the next non-empty line, `None` once the client has closed the connection
+pub fn read_line<R: BufRead>(
+    reader: &mut R,
+    max_len: usize
+) -> Result<Option<String>, Box<dyn Error>> {
+    loop {
+        // One byte past the limit tells a line that is too long from one that fits exactly
+        let mut line = Vec::new();
+        let n = reader.by_ref().take(max_len as u64 + 1).read_until(b'\n', &mut line)?;
+        if n == 0 {
+            return Ok(None);
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        } else if line.len() > max_len {
+            return Err(format!("Request line too long (max {} bytes)", max_len).into());
+        }
+
+        let line = String::from_utf8(line).map_err(|_| "Request is not UTF-8")?;
+        if !line.trim().is_empty() {
+            return Ok(Some(line.trim().to_string()));
+        }
+    }
+}
+
+// A connection read through a buffer and written directly. Lines, frames and raw
+// input must all be read through the same `BufStream`, since the buffer may already
+// hold the bytes that follow a line.
+pub struct BufStream<S: Read + Write> {
+    inner: BufReader<S>,
+}
+
+impl<S: Read + Write> BufStream<S> {
+    pub fn new(stream: S) -> Self {
+        BufStream { inner: BufReader::new(stream) }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.inner.get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        self.inner.get_mut()
+    }
+}
+
+impl<S: Read + Write> Read for BufStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: Read + Write> BufRead for BufStream<S> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<S: Read + Write> Write for BufStream<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.get_mut().flush()
+    }
+}
+
+pub fn parse_request(line: &str) -> Result<Value, String> {
+    let request: Value = serde_json::from_str(line).map_err(|e| format!("Invalid JSON request: {}", e))?;
+    if !request.is_object() {
+        return Err("Request must be a JSON object".to_string());
+    }
+    Ok(request)
+}
+
+pub fn write_response<W: Write>(
+    writer: &mut W,
+    id: &Value,
+    result: Result<Value, String>
+) -> Result<(), Box<dyn Error>> {
+    let response = match result {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "id": id, "ok": false, "error": error }),
+    };
+    writer.write_all(response.to_string().as_bytes())?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Name of the command of a request
+pub fn command_name(request: &Value) -> Option<&str> {
+    request.get("cmd").and_then(|c| c.as_str())
+}
+
+// Run one of the environment commands, charging transactions to `meter`.
+// `get_flag` depends on the challenge and is answered by the caller, see `Session::serve_commands`.
+pub async fn dispatch(
+    suitf: &mut SuiTF,
+    config: &CommandConfig,
+    meter: &mut SessionMeter,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let cmd = command_name(request).ok_or("Missing 'cmd'")?;
+    debug!(command = cmd, "JSON command");
+    meter.check_time()?;
+
+    match cmd {
+        "publish" => publish(suitf, config, meter, request).await,
+        "call" | "ptb" => {
+            meter.check_transaction()?;
+            let gas_before = suitf.get_gas_used();
+            let result = if cmd == "call" {
+                call(suitf, config, request).await
+            } else {
+                ptb(suitf, config, request).await
+            };
+            meter.charge_transaction(suitf, gas_before, 0, result.is_ok());
+            result
+        }
+        "view" => view(suitf, request).await,
+        "events" => events(suitf, request),
+        "objects" => Ok(objects(suitf)),
+        "dynamic_fields" => dynamic_fields(suitf, request).await,
+        "balance" => balance(suitf, config, request).await,
+        _ => Err(format!("Unknown command '{}'", cmd).into()),
+    }
+}
+
+fn str_field<'a>(
+    request: &'a Value,
+    name: &str
+) -> Result<&'a str, Box<dyn Error>> {
+    request
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("Missing '{}'", name).into())
+}
+
+// Account or package name, or a hex address
+pub fn parse_address(
+    suitf: &SuiTF,
+    text: &str
+) -> Result<AccountAddress, Box<dyn Error>> {
+    if let Some(addr) = suitf.get_account_address(text).or_else(|| suitf.get_package_address(text)) {
+        return Ok(addr);
+    }
+    AccountAddress::from_hex_literal(text).map_err(|_| format!("Unknown address '{}'", text).into())
+}
+
+// An object is given as its enumeration `[task, index]`, as an object ID or by its alias
+pub fn parse_object_id(
+    suitf: &SuiTF,
+    value: &Value
+) -> Result<FakeID, Box<dyn Error>> {
+    if let Some([task, idx]) = value.as_array().map(|a| a.as_slice()) {
+        if let (Some(task), Some(idx)) = (task.as_u64(), idx.as_u64()) {
+            return Ok(FakeID::Enumerated(task, idx));
+        }
+    }
+    if let Some(id) = value.as_str() {
+        if !id.starts_with("0x") {
+            return suitf.resolve_object(id);
+        }
+        let id = ObjectID::from_hex_literal(id).map_err(|e| format!("Bad object id '{}': {}", id, e))?;
+        return Ok(rpc::object_id_to_fake(id));
+    }
+    Err(format!("Bad object id {}, expected [task, index], a hex id or an alias", value).into())
+}
+
+pub fn fake_json(id: FakeID) -> Value {
+    match id {
+        FakeID::Enumerated(task, idx) => json!([task, idx]),
+        FakeID::Known(id) => json!(id.to_string()),
+    }
+}
+
+fn parse_number<T: FromStr>(value: &Value) -> Result<T, Box<dyn Error>> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => return Err(format!("Bad number {}", value).into()),
+    };
+    text.parse::<T>().map_err(|_| format!("Bad number {}", value).into())
+}
+
+// Convert a JSON argument into a value the adapter understands:
+//   123, true, "0x..."                  u64, bool and address shorthands
+//   {"u8": 1} ... {"u256": "1"}         sized integers, large ones as strings
+//   {"number": "1"}                     integer typed from its use in a transaction
+//   {"address": "0x.." | "solver"}      account, package or raw address
+//   {"string": "..."}                   `vector<u8>` / `String`
+//   {"vector": [...]}                   vector of any of these
+//   {"object": [task, idx] | "0x.." | "alias"}
+//                                       owned or mutable shared object
+//   {"imm_shared": ...}, {"receiving": ...}
+pub fn parse_value(
+    suitf: &SuiTF,
+    value: &Value
+) -> Result<ParsedValue<SuiExtraValueArgs>, Box<dyn Error>> {
+    let address = |addr: AccountAddress| {
+        ParsedValue::Address(ParsedAddress::Numerical(NumericalAddress::new(addr.into_bytes(), NumberFormat::Hex)))
+    };
+
+    let parsed = match value {
+        Value::Bool(b) => ParsedValue::Bool(*b),
+        Value::Number(_) => ParsedValue::U64(parse_number(value)?),
+        Value::String(s) => address(parse_address(suitf, s)?),
+        Value::Object(map) if map.len() == 1 => {
+            let (kind, inner) = map.iter().next().unwrap();
+            match kind.as_str() {
+                "bool" => ParsedValue::Bool(inner.as_bool().ok_or("Bad bool")?),
+                "u8" => ParsedValue::U8(parse_number(inner)?),
+                "u16" => ParsedValue::U16(parse_number(inner)?),
+                "u32" => ParsedValue::U32(parse_number(inner)?),
+                "u64" => ParsedValue::U64(parse_number(inner)?),
+                "u128" => ParsedValue::U128(parse_number(inner)?),
+                "u256" => {
+                    let text = inner.as_str().map(|s| s.to_string()).unwrap_or_else(|| inner.to_string());
+                    ParsedValue::U256(U256::from_str(&text).map_err(|_| format!("Bad u256 {}", inner))?)
+                }
+                "number" => {
+                    let text = inner.as_str().map(|s| s.to_string()).unwrap_or_else(|| inner.to_string());
+                    ParsedValue::InferredNum(U256::from_str(&text).map_err(|_| format!("Bad number {}", inner))?)
+                }
+                "address" => address(parse_address(suitf, inner.as_str().ok_or("Bad address")?)?),
+                "string" => ParsedValue::Vector(
+                    inner.as_str().ok_or("Bad string")?.bytes().map(ParsedValue::U8).collect()
+                ),
+                "vector" => {
+                    let elems = inner.as_array().ok_or("Bad vector")?;
+                    let mut values = Vec::with_capacity(elems.len());
+                    for elem in elems {
+                        values.push(parse_value(suitf, elem)?);
+                    }
+                    ParsedValue::Vector(values)
+                }
+                "object" => ParsedValue::Custom(SuiExtraValueArgs::Object(parse_object_id(suitf, inner)?, None)),
+                "imm_shared" => ParsedValue::Custom(SuiExtraValueArgs::ImmShared(parse_object_id(suitf, inner)?, None)),
+                "receiving" => ParsedValue::Custom(SuiExtraValueArgs::Receiving(parse_object_id(suitf, inner)?, None)),
+                _ => return Err(format!("Unknown argument kind '{}'", kind).into()),
+            }
+        }
+        _ => return Err(format!("Unsupported argument {}", value).into()),
+    };
+    Ok(parsed)
+}
+
+// JSON form of an adapter value, the inverse of `parse_value`. Object versions are dropped.
+pub fn value_json(value: &ParsedValue<SuiExtraValueArgs>) -> Result<Value, String> {
+    let json = match value {
+        ParsedValue::Address(ParsedAddress::Numerical(addr)) => json!({ "address": addr.into_inner().to_hex_literal() }),
+        ParsedValue::Address(ParsedAddress::Named(name)) => json!({ "address": name }),
+        ParsedValue::InferredNum(n) => json!({ "number": n.to_string() }),
+        ParsedValue::U8(n) => json!({ "u8": n }),
+        ParsedValue::U16(n) => json!({ "u16": n }),
+        ParsedValue::U32(n) => json!({ "u32": n }),
+        ParsedValue::U64(n) => json!({ "u64": n.to_string() }),
+        ParsedValue::U128(n) => json!({ "u128": n.to_string() }),
+        ParsedValue::U256(n) => json!({ "u256": n.to_string() }),
+        ParsedValue::Bool(b) => json!(b),
+        ParsedValue::Vector(elems) => {
+            let elems: Result<Vec<Value>, String> = elems.iter().map(value_json).collect();
+            json!({ "vector": elems? })
+        }
+        ParsedValue::Custom(SuiExtraValueArgs::Object(id, _)) => json!({ "object": fake_json(*id) }),
+        ParsedValue::Custom(SuiExtraValueArgs::ImmShared(id, _)) => json!({ "imm_shared": fake_json(*id) }),
+        ParsedValue::Custom(SuiExtraValueArgs::Receiving(id, _)) => json!({ "receiving": fake_json(*id) }),
+        other => return Err(format!("No JSON form for {:?}", other)),
+    };
+    Ok(json)
+}
+
+// JSON form of a Move call argument, see `value_json`
+pub fn sui_value_json(value: &SuiValue) -> Result<Value, String> {
+    let json = match value {
+        SuiValue::MoveValue(value) => move_value_json(value)?,
+        SuiValue::Object(id, _) => json!({ "object": fake_json(*id) }),
+        SuiValue::ObjVec(ids) => {
+            let elems: Vec<Value> = ids.iter().map(|(id, _)| json!({ "object": fake_json(*id) })).collect();
+            json!({ "vector": elems })
+        }
+        SuiValue::ImmShared(id, _) => json!({ "imm_shared": fake_json(*id) }),
+        SuiValue::Receiving(id, _) => json!({ "receiving": fake_json(*id) }),
+        other => return Err(format!("No JSON form for {:?}", other)),
+    };
+    Ok(json)
+}
+
+fn move_value_json(value: &MoveValue) -> Result<Value, String> {
+    let json = match value {
+        MoveValue::U8(n) => json!({ "u8": n }),
+        MoveValue::U16(n) => json!({ "u16": n }),
+        MoveValue::U32(n) => json!({ "u32": n }),
+        MoveValue::U64(n) => json!({ "u64": n.to_string() }),
+        MoveValue::U128(n) => json!({ "u128": n.to_string() }),
+        MoveValue::U256(n) => json!({ "u256": n.to_string() }),
+        MoveValue::Bool(b) => json!(b),
+        MoveValue::Address(addr) | MoveValue::Signer(addr) => json!({ "address": addr.to_hex_literal() }),
+        MoveValue::Vector(elems) => {
+            let elems: Result<Vec<Value>, String> = elems.iter().map(move_value_json).collect();
+            json!({ "vector": elems? })
+        }
+        other => return Err(format!("No JSON form for {:?}", other)),
+    };
+    Ok(json)
+}
+
+// Replace package and framework names in a type (`challenge::pool::Pool<sui::sui::SUI>`) by their address
+pub fn resolve_type_names(
+    suitf: &SuiTF,
+    type_: &str
+) -> String {
+    map_type_addresses(type_, |head| match suitf.get_package_address(head) {
+        Some(addr) => Some(addr.to_hex_literal()),
+        // The adapter prints framework types by name
+        None if head == "std" => Some("0x1".to_string()),
+        None if head == "sui" => Some("0x2".to_string()),
+        None => None,
+    })
+}
+
+// A type with every address as a short hex literal, whether it was written as a package,
+// framework or account name, a `fake(x,y)` placeholder or long hex, so that types can be
+// compared as text
+pub fn normalize_type(
+    suitf: &SuiTF,
+    type_: &str
+) -> String {
+    let resolved = resolve_type_names(suitf, type_);
+    map_type_addresses(&resolved, |head| {
+        let is_bare_hex = head.len() == 2 * AccountAddress::LENGTH && head.chars().all(|c| c.is_ascii_hexdigit());
+        if is_bare_hex {
+            Some(win::address_text(suitf, &format!("0x{}", head)))
+        } else {
+            Some(win::address_text(suitf, head))
+        }
+    })
+    .replace(' ', "")
+}
+
+// Rewrite the address of every `address::module::Name` in a type, addresses `address`
+// has no replacement for are kept
+fn map_type_addresses(
+    type_: &str,
+    address: impl Fn(&str) -> Option<String>
+) -> String {
+    let mut resolved = String::new();
+    let mut token = String::new();
+    let flush = |token: &mut String, resolved: &mut String| {
+        match token.split_once("::").and_then(|(head, rest)| Some((address(head)?, rest))) {
+            Some((addr, rest)) => resolved.push_str(&format!("{}::{}", addr, rest)),
+            None => resolved.push_str(token),
+        }
+        token.clear();
+    };
+
+    // `fake(x,y)` addresses hold a comma of their own
+    let mut parens = 0;
+    for c in type_.chars() {
+        match c {
+            '(' => {
+                parens += 1;
+                token.push(c);
+            }
+            ')' => {
+                parens -= 1;
+                token.push(c);
+            }
+            '<' | '>' | ',' | ' ' if parens == 0 => {
+                flush(&mut token, &mut resolved);
+                resolved.push(c);
+            }
+            _ => token.push(c),
+        }
+    }
+    flush(&mut token, &mut resolved);
+    resolved
+}
+
+// Parse a type, package names included (`challenge::coin::COIN`)
+pub fn parse_type_tag(
+    suitf: &SuiTF,
+    text: &str
+) -> Result<TypeTag, Box<dyn Error>> {
+    TypeTag::from_str(&resolve_type_names(suitf, text))
+        .map_err(|e| format!("Bad type argument '{}': {}", text, e).into())
+}
+
+fn parse_type_args(
+    suitf: &SuiTF,
+    request: &Value
+) -> Result<Vec<TypeTag>, Box<dyn Error>> {
+    let mut type_args = Vec::new();
+    for t in request.get("type_args").and_then(|t| t.as_array()).into_iter().flatten() {
+        let text = t.as_str().ok_or("Type arguments must be strings")?;
+        type_args.push(parse_type_tag(suitf, text)?);
+    }
+    Ok(type_args)
+}
+
+// Arguments of a Move call, see `parse_value`
+pub fn parse_args(
+    suitf: &SuiTF,
+    args: &[Value]
+) -> Result<Vec<SuiValue>, Box<dyn Error>> {
+    let mut values = Vec::new();
+    for arg in args {
+        let value = parse_value(suitf, arg)?
+            .into_concrete_value(&|name| suitf.get_account_address(name).or_else(|| suitf.get_package_address(name)))
+            .map_err(|e| format!("Bad argument {}: {}", arg, e))?;
+        values.push(value);
+    }
+    Ok(values)
+}
+
+// The only module of `package` that defines `function`
+pub fn find_module(
+    suitf: &SuiTF,
+    package: AccountAddress,
+    function: &str
+) -> Result<String, Box<dyn Error>> {
+    let modules: Vec<String> = suitf
+        .get_package_modules(package)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| {
+            suitf
+                .get_module_abi(package, m)
+                .map(|abi| abi.function(function).is_some())
+                .unwrap_or(false)
+        })
+        .collect();
+
+    match modules.as_slice() {
+        [module] => Ok(module.clone()),
+        [] => Err(format!("No module of {} defines '{}'", package.to_hex_literal(), function).into()),
+        _ => Err(format!(
+            "Several modules of {} define '{}' ({}), give the module",
+            package.to_hex_literal(),
+            function,
+            modules.join(", ")
+        ).into()),
+    }
+}
+
+// Object changes and events of a transaction, for the response
+fn effects_json(
+    suitf: &SuiTF,
+    output: Option<&str>,
+    events_before: usize
+) -> Value {
+    let mut changes = serde_json::Map::new();
+    for (kind, refs) in output::parse_object_changes(output.unwrap_or_default()) {
+        changes.insert(kind, json!(refs));
+    }
+    json!({
+        "changes": changes,
+        "events": &suitf.get_events()[events_before..],
+        "output": output,
+    })
+}
+
+async fn publish(
+    suitf: &mut SuiTF,
+    config: &CommandConfig,
+    meter: &mut SessionMeter,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let mut upload = protocol::PackageUpload {
+        named_address: request.get("named_address").and_then(|n| n.as_str()).map(|n| n.to_string()),
+        ..Default::default()
+    };
+    for dep in request.get("dependencies").and_then(|d| d.as_array()).into_iter().flatten() {
+        upload.dependencies.push(dep.as_str().ok_or("Dependencies must be strings")?.to_string());
+    }
+
+    let modules = request.get("modules").and_then(|m| m.as_array()).ok_or("Missing 'modules'")?;
+    if modules.is_empty() {
+        return Err("Upload contains no modules".into());
+    }
+    let mut total_size = 0;
+    for module in modules {
+        let name = str_field(module, "name")?.to_string();
+        let bytes = BASE64
+            .decode(str_field(module, "bytes")?)
+            .map_err(|e| format!("Module '{}' is not base64: {}", name, e))?;
+        total_size += bytes.len();
+        upload.modules.push(protocol::ModuleUpload { name, bytes });
+    }
+    if total_size > config.max_upload_size {
+        return Err(format!("Upload too large: {} bytes (max {})", total_size, config.max_upload_size).into());
+    }
+
+    let module_count = upload.modules.len();
+    meter.check_publish(module_count)?;
+    let gas_before = suitf.get_gas_used();
+    let result = server::publish_upload(
+        suitf,
+        upload,
+        &config.default_named_address,
+        &config.allowed_dependencies,
+        &config.player,
+    ).await;
+    meter.charge_transaction(suitf, gas_before, module_count, result.is_ok());
+    let published = result?;
+
+    Ok(json!({
+        "named_address": published.named_address,
+        "address": published.address.to_hex_literal(),
+        "modules": published.modules,
+    }))
+}
+
+async fn call(
+    suitf: &mut SuiTF,
+    config: &CommandConfig,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let package = parse_address(suitf, str_field(request, "package")?)?;
+    let function = str_field(request, "function")?;
+
+    // The module can be left out when a single module of the package defines the function
+    let module = match request.get("module").and_then(|m| m.as_str()) {
+        Some(module) => module.to_string(),
+        None => find_module(suitf, package, function)?,
+    };
+
+    let args = match request.get("args") {
+        Some(Value::Array(args)) => parse_args(suitf, args)?,
+        Some(Value::Null) | None => Vec::new(),
+        Some(_) => return Err("'args' must be an array".into()),
+    };
+    let type_args = parse_type_args(suitf, request)?;
+
+    let events_before = suitf.get_events().len();
+    let output = suitf.call_function(
+        package,
+        &module,
+        function,
+        args,
+        type_args,
+        Some(config.player.clone()),
+    ).await?;
+
+    Ok(effects_json(suitf, output.as_deref(), events_before))
+}
+
+async fn view(
+    suitf: &mut SuiTF,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let id = parse_object_id(suitf, request.get("id").ok_or("Missing 'id'")?)?;
+    let raw = suitf
+        .view_object_raw(id)
+        .await?
+        .ok_or_else(|| format!("Object {} not found", fake_json(id)))?;
+
+    Ok(json!({
+        "id": fake_json(id),
+        "alias": suitf.alias_of(id),
+        "type": output::parse_object_type(&raw),
+        "owner": output::parse_object_owner(&raw),
+        "version": output::parse_object_version(&raw),
+        "fields": SuiTF::parse_output(&raw).get("Contents").cloned().unwrap_or(Value::Null),
+    }))
+}
+
+// Dynamic fields of `id`, or of its collection `field`, only the one named `key` when given
+async fn dynamic_fields(
+    suitf: &mut SuiTF,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let id = parse_object_id(suitf, request.get("id").ok_or("Missing 'id'")?)?;
+    let field = request.get("field").and_then(|f| f.as_str());
+    let fields: Vec<_> = match request.get("key") {
+        Some(key) => suitf.dynamic_field(id, field, key).await?.into_iter().collect(),
+        None => suitf.dynamic_fields(id, field).await?,
+    };
+    Ok(json!({ "fields": fields.iter().map(|f| f.to_json()).collect::<Vec<_>>() }))
+}
+
+// Objects created so far, named ones first
+fn objects(suitf: &SuiTF) -> Value {
+    let aliases: Vec<Value> = suitf
+        .get_aliases()
+        .iter()
+        .map(|(name, &id)| json!({ "alias": name, "id": fake_json(id) }))
+        .collect();
+    let known: Vec<Value> = suitf.get_known_objects().into_iter().map(fake_json).collect();
+    json!({ "aliases": aliases, "objects": known })
+}
+
+async fn ptb(
+    suitf: &mut SuiTF,
+    config: &CommandConfig,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let mut inputs = Vec::new();
+    for input in request.get("inputs").and_then(|i| i.as_array()).into_iter().flatten() {
+        inputs.push(parse_value(suitf, input)?);
+    }
+
+    let mut commands = Vec::new();
+    for command in request.get("commands").and_then(|c| c.as_array()).ok_or("Missing 'commands'")? {
+        let command = command.as_str().ok_or("Commands must be strings")?;
+        if command.contains('\n') {
+            return Err("Commands cannot span several lines".into());
+        }
+        commands.push(command.to_string());
+    }
+
+    let gas_budget = request.get("gas_budget").and_then(|g| g.as_u64());
+    let dry_run = request.get("dry_run").and_then(|d| d.as_bool()).unwrap_or(false);
+
+    let events_before = suitf.get_events().len();
+    let output = suitf.programmable_transaction(
+        config.player.clone(),
+        inputs,
+        commands,
+        gas_budget,
+        dry_run,
+    ).await?;
+
+    Ok(effects_json(suitf, output.as_deref(), events_before))
+}
+
+fn events(
+    suitf: &SuiTF,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let all = suitf.get_events();
+    let since = (request.get("since").and_then(|s| s.as_u64()).unwrap_or(0) as usize).min(all.len());
+    Ok(json!({
+        "events": &all[since..],
+        "next": all.len(),
+    }))
+}
+
+async fn balance(
+    suitf: &mut SuiTF,
+    config: &CommandConfig,
+    request: &Value
+) -> Result<Value, Box<dyn Error>> {
+    let owner_name = request.get("owner").and_then(|o| o.as_str()).unwrap_or(&config.player);
+    let owner = parse_address(suitf, owner_name)?;
+    let coin_type = resolve_type_names(
+        suitf,
+        request.get("coin_type").and_then(|t| t.as_str()).unwrap_or(SUI_COIN_TYPE),
+    );
+
+    let total = suitf.get_balance(owner, &coin_type).await?;
+    Ok(json!({
+        "owner": owner.to_hex_literal(),
+        "coin_type": coin_type,
+        "balance": total.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::with_session;
+
+    fn parse(
+        suitf: &SuiTF,
+        value: Value
+    ) -> ParsedValue<SuiExtraValueArgs> {
+        parse_value(suitf, &value).unwrap_or_else(|e| panic!("{} rejected: {}", value, e))
+    }
+
+    fn address_of(value: &ParsedValue<SuiExtraValueArgs>) -> Option<AccountAddress> {
+        match value {
+            ParsedValue::Address(ParsedAddress::Numerical(addr)) => Some(addr.into_inner()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_values() {
+        with_session(|suitf| {
+            assert!(matches!(parse(suitf, json!(true)), ParsedValue::Bool(true)));
+            assert!(matches!(parse(suitf, json!(5)), ParsedValue::U64(5)));
+            assert!(matches!(parse(suitf, json!({ "u8": 7 })), ParsedValue::U8(7)));
+            assert!(matches!(parse(suitf, json!({ "u16": "300" })), ParsedValue::U16(300)));
+            assert!(matches!(
+                parse(suitf, json!({ "u128": "340282366920938463463374607431768211455" })),
+                ParsedValue::U128(u128::MAX)
+            ));
+            assert!(matches!(parse(suitf, json!({ "u256": "1" })), ParsedValue::U256(n) if n == U256::from(1u64)));
+            assert!(matches!(parse(suitf, json!({ "number": 9 })), ParsedValue::InferredNum(n) if n == U256::from(9u64)));
+
+            match parse(suitf, json!({ "string": "hi" })) {
+                ParsedValue::Vector(bytes) => {
+                    assert!(matches!(bytes.as_slice(), [ParsedValue::U8(b'h'), ParsedValue::U8(b'i')]));
+                }
+                other => panic!("string parsed as {:?}", other),
+            }
+            match parse(suitf, json!({ "vector": [{ "u16": 1 }, { "vector": [] }] })) {
+                ParsedValue::Vector(elems) => {
+                    assert!(matches!(elems.as_slice(), [ParsedValue::U16(1), ParsedValue::Vector(inner)] if inner.is_empty()));
+                }
+                other => panic!("vector parsed as {:?}", other),
+            }
+
+            let solver = suitf.get_account_address("solver").unwrap();
+            assert_eq!(address_of(&parse(suitf, json!({ "address": "solver" }))), Some(solver));
+            assert_eq!(address_of(&parse(suitf, json!("solver"))), Some(solver));
+            assert_eq!(address_of(&parse(suitf, json!("0x2"))), Some(AccountAddress::TWO));
+        });
+    }
+
+    #[test]
+    fn parses_objects() {
+        with_session(|suitf| {
+            assert!(matches!(
+                parse(suitf, json!({ "object": [2, 1] })),
+                ParsedValue::Custom(SuiExtraValueArgs::Object(FakeID::Enumerated(2, 1), None))
+            ));
+            assert!(matches!(
+                parse(suitf, json!({ "imm_shared": [3, 0] })),
+                ParsedValue::Custom(SuiExtraValueArgs::ImmShared(FakeID::Enumerated(3, 0), None))
+            ));
+            assert!(matches!(
+                parse(suitf, json!({ "receiving": [4, 2] })),
+                ParsedValue::Custom(SuiExtraValueArgs::Receiving(FakeID::Enumerated(4, 2), None))
+            ));
+
+            // Synthetic object IDs of the JSON-RPC endpoint stand for the same objects
+            let hex = rpc::fake_to_object_id(2, 1).to_string();
+            assert!(matches!(
+                parse(suitf, json!({ "object": hex })),
+                ParsedValue::Custom(SuiExtraValueArgs::Object(FakeID::Enumerated(2, 1), None))
+            ));
+
+            suitf.alias("pool", FakeID::Enumerated(5, 0)).unwrap();
+            assert!(matches!(
+                parse(suitf, json!({ "object": "pool" })),
+                ParsedValue::Custom(SuiExtraValueArgs::Object(FakeID::Enumerated(5, 0), None))
+            ));
+            assert!(parse_value(suitf, &json!({ "object": "nothing" })).is_err());
+        });
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        with_session(|suitf| {
+            for value in [
+                json!(null),
+                json!(-1),
+                json!(1.5),
+                json!({ "u8": 256 }),
+                json!({ "u64": "ten" }),
+                json!({ "bool": 1 }),
+                json!({ "string": 1 }),
+                json!({ "vector": { "u8": 1 } }),
+                json!({ "object": [1] }),
+                json!({ "u8": 1, "u16": 2 }),
+                json!({ "float": 1 }),
+                json!({ "address": "nobody" }),
+            ] {
+                assert!(parse_value(suitf, &value).is_err(), "{} accepted", value);
+            }
+        });
+    }
+
+    #[test]
+    fn round_trips_values() {
+        with_session(|suitf| {
+            for value in [
+                json!(true),
+                json!({ "u8": 7 }),
+                json!({ "u64": "5" }),
+                json!({ "u256": "1" }),
+                json!({ "number": "9" }),
+                json!({ "vector": [{ "u16": 1 }, { "u16": 2 }] }),
+                json!({ "object": [2, 1] }),
+                json!({ "imm_shared": [3, 0] }),
+                json!({ "address": "0x2" }),
+            ] {
+                assert_eq!(value_json(&parse(suitf, value.clone())).unwrap(), value);
+            }
+        });
+    }
+
+    #[test]
+    fn maps_type_addresses() {
+        let to_hex = |head: &str| match head {
+            "challenge" => Some("0xc".to_string()),
+            "fake(1,2)" => Some("0xf".to_string()),
+            _ => None,
+        };
+        assert_eq!(map_type_addresses("challenge::pool::Pool", to_hex), "0xc::pool::Pool");
+        assert_eq!(
+            map_type_addresses("challenge::pool::Pool<sui::sui::SUI, fake(1,2)::coin::COIN>", to_hex),
+            "0xc::pool::Pool<sui::sui::SUI, 0xf::coin::COIN>"
+        );
+        assert_eq!(
+            map_type_addresses("vector<challenge::pool::Pool<u64>>", to_hex),
+            "vector<0xc::pool::Pool<u64>>"
+        );
+        // Primitive types and unknown addresses are kept
+        assert_eq!(map_type_addresses("u64", to_hex), "u64");
+        assert_eq!(map_type_addresses("other::m::T<fake(3,4)::m::U>", to_hex), "other::m::T<fake(3,4)::m::U>");
+    }
+
+    #[test]
+    fn normalizes_types() {
+        with_session(|suitf| {
+            let coin = "0x2::coin::Coin<0x2::sui::SUI>";
+            // Names as the adapter prints them, long and bare hex addresses
+            assert_eq!(normalize_type(suitf, "sui::coin::Coin<sui::sui::SUI>"), coin);
+            assert_eq!(
+                normalize_type(suitf, "0x0000000000000000000000000000000000000000000000000000000000000002::coin::Coin< 0x2::sui::SUI >"),
+                coin
+            );
+            assert_eq!(
+                normalize_type(suitf, "0000000000000000000000000000000000000000000000000000000000000002::coin::Coin<sui::sui::SUI>"),
+                coin
+            );
+
+            // `fake(x,y)` placeholders become the object ID the endpoint gives them
+            let fake = AccountAddress::new(rpc::fake_to_object_id(1, 0).into_bytes()).to_hex_literal();
+            assert_eq!(
+                normalize_type(suitf, "0x2::coin::Coin<fake(1,0)::token::TOKEN>"),
+                format!("0x2::coin::Coin<{}::token::TOKEN>", fake)
+            );
+            assert_eq!(normalize_type(suitf, "vector<u8>"), "vector<u8>");
+        });
+    }
+}
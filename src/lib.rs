@@ -1,12 +1,39 @@
-use std::collections::{BTreeMap, HashMap};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::io::Write;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::fs::File;
 use std::error;
 
+pub mod abi;
+pub mod abort;
+pub mod alias;
+pub mod client;
+pub mod dynamic;
+pub mod flag;
+pub mod fork;
+pub mod harness;
+pub mod jsonl;
+pub mod limits;
+pub mod logging;
+pub mod manifest;
+pub mod output;
+pub mod package;
+pub mod pool;
+pub mod pow;
+pub mod protocol;
+pub mod rpc;
+pub mod server;
+#[cfg(test)]
+mod testing;
+pub mod transactional;
+pub mod transcript;
+pub mod win;
+pub mod worker;
+
 use once_cell::sync::Lazy;
 use tempfile::NamedTempFile;
+use tracing::{debug, error, info, instrument, warn};
 use serde_json::Value;
 
 use sui_graphql_rpc::test_infra::cluster::SnapshotLagConfig;
@@ -14,6 +41,7 @@ use sui_transactional_test_runner::{
     args::{
         SuiInitArgs, 
         SuiPublishArgs, 
+        SuiExtraValueArgs,
         SuiRunArgs, 
         SuiSubcommand, 
         SuiValue, 
@@ -41,6 +69,9 @@ pub use move_compiler::{
     shared::{NumberFormat, NumericalAddress, PackagePaths},
     Flags, FullyCompiledProgram, construct_pre_compiled_lib
 };
+use move_binary_format::file_format::CompiledModule;
+use move_bytecode_source_map::source_map::SourceMap;
+use move_command_line_common::files::FileHash;
 use move_core_types::{
     account_address::AccountAddress,
     identifier::{IdentStr, Identifier},
@@ -49,7 +80,7 @@ use move_core_types::{
 };
 use move_transactional_test_runner::{
     framework::{MoveTestAdapter, MaybeNamedCompiledModule, store_modules},
-    tasks::{InitCommand, SyntaxChoice, TaskInput},
+    tasks::{InitCommand, SyntaxChoice, TaskCommand, TaskInput},
 };
 
 static NAMED_ADDRESSES: Lazy<BTreeMap<String, NumericalAddress>> = Lazy::new(|| {
@@ -63,11 +94,63 @@ static NAMED_ADDRESSES: Lazy<BTreeMap<String, NumericalAddress>> = Lazy::new(||
     map
 });
 
+// Where the compiled Sui framework and Move stdlib used to compile Move sources (e.g.
+// transactional test tasks) come from. `Local` is a checkout of
+// `crates/sui-framework/packages` with `Sui` and `MoveStdlib` directories; it should
+// match the framework bundled with the adapter, which is what gets published.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum FrameworkSource {
+    #[default]
+    Bundled,
+    Local(PathBuf),
+}
+
+// One compiled framework per source, shared by every session
+static PRECOMPILED: Lazy<Mutex<HashMap<FrameworkSource, Arc<FullyCompiledProgram>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Compiled framework of `source`, compiled or copied once and then shared
+pub fn precompiled_framework(
+    source: &FrameworkSource
+) -> Result<Arc<FullyCompiledProgram>, Box<dyn error::Error>> {
+    // Held while compiling, other sessions would wait for the same program anyway
+    let mut cache = PRECOMPILED.lock().map_err(|_| "Precompiled framework cache is poisoned")?;
+    if let Some(program) = cache.get(source) {
+        return Ok(program.clone());
+    }
+
+    let program = match source {
+        FrameworkSource::Bundled => Arc::new(PRE_COMPILED.clone()),
+        FrameworkSource::Local(dir) => {
+            info!(path = %dir.display(), "compiling local Sui framework");
+            Arc::new(SuiTF::_get_precompiled(dir)?)
+        }
+    };
+    cache.insert(source.clone(), program.clone());
+    Ok(program)
+}
+
 // Sui CTF framework environment
 pub struct SuiTF {
     adapter: SuiTestAdapter,
     account_map: HashMap<AccountAddress, String>,
     package_map: HashMap<String, AccountAddress>,
+    module_map: HashMap<AccountAddress, Vec<CompiledModule>>,
+    // Source maps and source files of published modules, to decode aborts
+    source_maps: HashMap<(AccountAddress, String), SourceMap>,
+    sources: HashMap<FileHash, abort::SourceFile>,
+    known_objects: BTreeSet<(u64, u64)>,
+    // UID each known object hangs from as a dynamic field, see `dynamic::list`
+    field_parents: HashMap<(u64, u64), Option<String>>,
+    // Object names, and rules naming objects as they are created, see `alias`
+    aliases: BTreeMap<String, FakeID>,
+    alias_rules: Vec<alias::AliasRule>,
+    events: Vec<output::EventRecord>,
+    gas_used: u64,
+    // Every operation run so far, starting with the initialization
+    history: Vec<transcript::Record>,
+    recorder: Option<transcript::Recorder>,
+    framework: FrameworkSource,
 }
 
 impl SuiTF {
@@ -111,7 +194,7 @@ impl SuiTF {
         // Handle compilation diagnostics
         match fully_compiled_res_inner {
             Err((_files, _diags)) => {
-                eprintln!("[*] Sui framework failed to compile!");
+                error!("Sui framework failed to compile");
                 // report_diagnostics(&files, diags);
                 Err(Box::new(std::io::Error::new(
                     std::io::ErrorKind::Other, 
@@ -127,6 +210,16 @@ impl SuiTF {
     pub async fn initialize<'a>(
         named_addresses: Vec<(String, NumericalAddress)>,
         accounts: Option<Vec<String>>,
+    ) -> Result<SuiTF, Box<dyn error::Error>> {
+        SuiTF::initialize_with_framework(named_addresses, accounts, &FrameworkSource::Bundled).await
+    }
+
+    // Same as `initialize`, compiling Move sources against the framework of `framework`
+    #[instrument(name = "initialize", skip_all, fields(accounts = ?accounts, framework = ?framework))]
+    pub async fn initialize_with_framework(
+        named_addresses: Vec<(String, NumericalAddress)>,
+        accounts: Option<Vec<String>>,
+        framework: &FrameworkSource,
     ) -> Result<SuiTF, Box<dyn error::Error>> { 
         // Initialize the SuiTestAdapter with optional accounts and default protocol version
         // let protocol_version = Some(ProtocolConfig::get_for_version(ProtocolVersion::MAX, Chain::Unknown).version.as_u64());
@@ -141,7 +234,7 @@ impl SuiTF {
                 named_addresses: named_addresses.clone() 
             }, 
             SuiInitArgs { 
-                accounts,
+                accounts: accounts.clone(),
                 protocol_version,
                 max_gas: None,
                 shared_object_deletion: None,
@@ -177,18 +270,18 @@ impl SuiTF {
         });
 
         let default_syntax = SyntaxChoice::Source;
-        let fully_compiled_program_opt = Some(Arc::new(PRE_COMPILED.clone()));
+        let fully_compiled_program_opt = Some(precompiled_framework(framework)?);
 
         // Perform initialization (publishing frameworks and creating accounts)
-        let (adapter, result_opt) = SuiTestAdapter::init(
+        let (mut adapter, result_opt) = SuiTestAdapter::init(
             default_syntax, 
             fully_compiled_program_opt, 
             init_opt, 
             Path::new("")
         ).await;
 
-        println!("[*] Initialization Result: {:#?}", result_opt);
-        println!("[*] Successfully Initialized");
+        debug!(output = ?result_opt, "initialization result");
+        info!("initialized");
 
         let mut account_map = HashMap::new();
         for (name, num_addr) in named_addresses.iter() {
@@ -196,20 +289,59 @@ impl SuiTF {
             account_map.insert(addr, name.clone());
         }
 
-        let sui_tf = SuiTF {
+        // Accounts created by the adapter are registered as named addresses
+        for name in accounts.clone().unwrap_or_default().iter() {
+            if let Some(num_addr) = adapter.compiled_state().named_address_mapping.get(name) {
+                account_map.insert(num_addr.into_inner(), name.clone());
+            }
+        }
+
+        let mut sui_tf = SuiTF {
             adapter,
             account_map,
             package_map: HashMap::new(),
+            module_map: HashMap::new(),
+            source_maps: HashMap::new(),
+            sources: HashMap::new(),
+            known_objects: BTreeSet::new(),
+            field_parents: HashMap::new(),
+            aliases: BTreeMap::new(),
+            alias_rules: Vec::new(),
+            events: Vec::new(),
+            gas_used: 0,
+            history: Vec::new(),
+            recorder: None,
+            framework: framework.clone(),
         };
+        if let Some(output) = result_opt.as_deref() {
+            sui_tf.record_output(output);
+        }
+        sui_tf.record_operation(transcript::Operation::init(&named_addresses, &accounts, framework), Ok(result_opt));
 
         Ok(sui_tf)
     }
 
+    #[instrument(name = "publish", skip_all, fields(modules = modules.len(), sender = ?sender))]
     pub async fn publish_compiled_module(
         &mut self, 
         modules: Vec<MaybeNamedCompiledModule>, 
         module_dependencies: Vec<String>, 
         sender: Option<String>
+    ) -> Result<AccountAddress, Box<dyn error::Error>>  {
+        let operation = transcript::Operation::publish(&modules, &module_dependencies, &sender);
+        let result = self.publish_modules(modules, module_dependencies, sender).await;
+        self.record_operation(
+            operation,
+            result.as_ref().map(|addr| Some(addr.to_hex_literal())).map_err(|e| e.to_string()),
+        );
+        result
+    }
+
+    async fn publish_modules(
+        &mut self, 
+        modules: Vec<MaybeNamedCompiledModule>, 
+        module_dependencies: Vec<String>, 
+        sender: Option<String>
     ) -> Result<AccountAddress, Box<dyn error::Error>>  {
         if modules.is_empty() {
             return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, "No modules to publish")));
@@ -228,7 +360,7 @@ impl SuiTF {
         let (output, published_modules) = match result {
             Ok(res) => res,
             Err(e) => {
-                eprintln!("[!] Failed to publish modules: {:?}", e);
+                warn!(error = ?e, "publish failed");
                 return Err(e.into());
             }
         };
@@ -240,18 +372,34 @@ impl SuiTF {
         // Store the published modules and retrieve the address
         let package_name = published_modules[0].named_address.clone().unwrap().as_str().to_string();
         let published_address = published_modules[0].module.address_identifiers[0];
+        let compiled_modules: Vec<CompiledModule> = published_modules
+            .iter()
+            .map(|m| m.module.clone())
+            .collect();
+        for module in published_modules.iter() {
+            if let Some(source_map) = &module.source_map {
+                self.source_maps.insert((published_address, module.module.name().to_string()), source_map.clone());
+            }
+        }
         let default_syntax = SyntaxChoice::Source;
         let data = NamedTempFile::new().expect("Failed to create temp file for modules");
         store_modules(&mut self.adapter, default_syntax, data, published_modules);
 
-        println!("[*] Successfully published package '{}' at {:?}", package_name, published_address);
-        println!("[*] Publish output: {:#?}\n", output.unwrap_or_else(|| "<no output>".to_string()));
-        
+        info!(package = %package_name, address = %published_address, "published package");
+        debug!(output = ?output, "publish output");
+
+        if let Some(output) = output.as_deref() {
+            self.record_output(output);
+            self.name_created_objects(output).await;
+        }
+
         self.package_map.insert(package_name, published_address);
+        self.module_map.insert(published_address, compiled_modules);
 
         Ok(published_address)
     }
 
+    #[instrument(name = "call", skip_all, fields(package = %mod_addr, module = mod_name, function = fun_name, sender = ?signer))]
     pub async fn call_function(
         &mut self,
         mod_addr: AccountAddress,
@@ -261,6 +409,8 @@ impl SuiTF {
         type_args: Vec<TypeTag>,
         signer: Option<String>,
     ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let operation = transcript::Operation::call(mod_addr, mod_name, fun_name, &args, &type_args, &signer);
+
         // Prepare module and function identifiers
         let module_id = ModuleId::new(mod_addr, Identifier::new(mod_name).map_err(|e| -> Box<dyn std::error::Error> { e.into() })?);
         let function: &IdentStr = IdentStr::new(fun_name).map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
@@ -277,21 +427,96 @@ impl SuiTF {
             &module_id, function, type_args, signers, args, gas_budget, extra_args,
         ).await {
             Ok((output, _return_values)) => {
-                println!("[*] Successfully called {}", fun_name);
-                println!("[*] Call output: {:#?}", output.clone().unwrap_or_else(|| "<empty>".to_string()));
+                info!("call succeeded");
+                debug!(output = ?output, "call output");
+                if let Some(output) = output.as_deref() {
+                    self.record_output(output);
+                    self.name_created_objects(output).await;
+                }
+                self.record_operation(operation, Ok(output.clone()));
                 Ok(output)
             }
             Err(err) => {
-                eprintln!("[!] Failed to call function: {:?}", err);
-                Err(err.into())
+                warn!(error = ?err, "call failed");
+                self.record_operation(operation, Err(err.to_string()));
+                Err(self.abort_error(err.into()))
             }
         }
     }
 
     pub async fn view_object(
         &mut self, 
-        id: FakeID
+        id: impl Into<alias::ObjectKey>
     ) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let out = self.view_object_raw(id).await?;
+        Ok(out.map(|output_str| Self::parse_output(&output_str)))
+    }
+
+    pub async fn view_object_raw(
+        &mut self, 
+        id: impl Into<alias::ObjectKey>
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let id = self.resolve_object(id)?;
+        self.view_fake_object(id).await
+    }
+
+    // Dynamic fields of an object, or of its `Table`, `Bag`, ... `field`, see `dynamic`
+    pub async fn dynamic_fields(
+        &mut self,
+        parent: impl Into<alias::ObjectKey>,
+        field: Option<&str>
+    ) -> Result<Vec<dynamic::DynamicField>, Box<dyn std::error::Error>> {
+        dynamic::list(self, parent, field).await
+    }
+
+    // Dynamic field named `key`, given in the JSON-lines syntax (`5`, `"solver"`, ...)
+    pub async fn dynamic_field(
+        &mut self,
+        parent: impl Into<alias::ObjectKey>,
+        field: Option<&str>,
+        key: &serde_json::Value
+    ) -> Result<Option<dynamic::DynamicField>, Box<dyn std::error::Error>> {
+        dynamic::get(self, parent, field, key).await
+    }
+
+    #[instrument(name = "view", skip_all, fields(object = ?id))]
+    async fn view_fake_object(
+        &mut self,
+        id: FakeID
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let operation = transcript::Operation::View { object: jsonl::fake_json(id) };
+        let result = self.view_task(id).await;
+        match &result {
+            Ok(out) => {
+                debug!(output = ?out, "viewed object");
+                self.record_operation(operation, Ok(out.clone()));
+            }
+            Err(err) => {
+                warn!(error = %err, "view failed");
+                self.record_operation(operation, Err(err.to_string()));
+            }
+        }
+        result
+    }
+
+    // View for the framework's own scans and checks (alias rules, balances, win
+    // conditions, dynamic fields), recorded as a `lookup` rather than a player's `view`.
+    // Like any adapter task it takes a task number, so objects created afterwards are
+    // numbered past it: it is recorded so that replays and exports number them the same.
+    async fn lookup_object(
+        &mut self,
+        id: FakeID
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let operation = transcript::Operation::Lookup { object: jsonl::fake_json(id) };
+        let result = self.view_task(id).await;
+        self.record_operation(operation, result.as_ref().cloned().map_err(|e| e.to_string()));
+        result
+    }
+
+    async fn view_task(
+        &mut self,
+        id: FakeID
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
         // Construct the command to view an object by its ID
         let command_text = "run".to_string();
         let task_text = "//#".to_owned() + &command_text.replace('\n', "\n//#");
@@ -307,22 +532,9 @@ impl SuiTF {
         };
 
         // Execute the view command
-        match self.adapter.handle_subcommand(arg_view).await {
-            Ok(out) => {
-                println!("[*] Successfully viewed object {:#?}", id);
-                if let Some(output_str) = out {
-                    let parsed_output = Self::parse_output(&output_str);
-                    Ok(Some(parsed_output))
-                } else {
-                    Ok(None)
-                }
-            }
-            Err(err) => {
-                eprintln!("[!] Failed to view object: {:?}", err);
-                Err(err.into())
-            }
-        }
+        self.adapter.handle_subcommand(arg_view).await.map_err(|err| err.into())
     }
+
     fn parse_output(
         output: &str
     ) -> Value {
@@ -346,56 +558,56 @@ impl SuiTF {
         Value::Object(result)
     }
 
+    // Fields up to the line closing the struct, `}` or `},` once nested, so inner structs
+    // keep the fields that follow them
     fn parse_nested_object(
         lines: &mut std::str::Lines, 
         initial_value: &str
     ) -> Value {
         let mut nested_result = serde_json::Map::new();
-        let mut buffer = String::new();
 
-        if !initial_value.trim_end().ends_with('}') {
-            buffer.push_str(initial_value);
+        if initial_value.trim_end().ends_with('}') {
+            return Value::Object(nested_result);
         }
 
         while let Some(line) = lines.next() {
-            buffer.push_str("\n");
-            buffer.push_str(line.trim());
-
-            if line.trim().ends_with('}') {
+            let line = line.trim();
+            if line.starts_with('}') {
                 break;
             }
-        }
 
-        let inner_content = buffer
-            .trim_start_matches('{')
-            .trim_end_matches('}')
-            .trim();
-
-        let mut inner_lines = inner_content.lines();
-
-        while let Some(line) = inner_lines.next() {
             if let Some((key, value)) = line.split_once(": ") {
                 let key = key.trim().to_string();
                 let value = value.trim();
 
                 if value.ends_with('{') {
-                    let nested_object = Self::parse_nested_object(&mut inner_lines, value);
+                    let nested_object = Self::parse_nested_object(lines, value);
                     nested_result.insert(key, nested_object);
                 } else {
                     nested_result.insert(key, Value::String(value.to_string()));
                 }
+            } else if line.ends_with('{') {
+                // Structs inside vectors have no key, skip them as a whole
+                Self::parse_nested_object(lines, line);
             }
         }
 
         Value::Object(nested_result)
     }
 
+    #[instrument(name = "fund", skip_all, fields(account = %account_address, amount = amount, sender = %sender))]
     pub async fn fund_account(
         &mut self,
         account_address: String,
         amount: u64,
         sender: AccountAddress
     ) -> Result<(), Box<dyn error::Error>> {
+        let operation = transcript::Operation::Fund {
+            account: account_address.clone(),
+            amount,
+            sender: sender.to_hex_literal(),
+        };
+
         // Prepare inputs for a programmable transaction to fund an address
         let mut input = vec![];
         input.push(ParsedValue::InferredNum(U256::from(amount)));
@@ -435,17 +647,175 @@ impl SuiTF {
         // Execute the funding transaction
         match self.adapter.handle_subcommand(arg_view).await {
             Ok(out) => {
-                println!("[*] Successfully funded account '{}' with {}", account_address, amount);
-                println!("[*] Fund transaction output: {:#?}", out.unwrap_or_else(|| "<no output>".to_string()));
+                info!("funded account");
+                debug!(output = ?out, "fund output");
+                if let Some(output) = out.as_deref() {
+                    self.record_output(output);
+                    self.name_created_objects(output).await;
+                }
+                self.record_operation(operation, Ok(None));
                 Ok(())
             }
             Err(err) => {
-                eprintln!("[!] Failed to fund address: {:?}", err);
+                warn!(error = ?err, "funding failed");
+                self.record_operation(operation, Err(err.to_string()));
                 Err(err.into())
             }
         }
     }
 
+    #[instrument(name = "ptb", skip_all, fields(sender = %sender, commands = commands.len(), dry_run = dry_run))]
+    pub async fn programmable_transaction(
+        &mut self,
+        sender: String,
+        inputs: Vec<ParsedValue<SuiExtraValueArgs>>,
+        commands: Vec<String>,
+        gas_budget: Option<u64>,
+        dry_run: bool,
+    ) -> Result<Option<String>, Box<dyn error::Error>> {
+        if commands.is_empty() {
+            return Err("No commands in programmable transaction".into());
+        }
+
+        let operation = transcript::Operation::Ptb {
+            sender: sender.clone(),
+            inputs: inputs.iter().map(|v| jsonl::value_json(v).unwrap_or(Value::Null)).collect(),
+            commands: commands.clone(),
+            gas_budget,
+            dry_run,
+        };
+
+        // Write the commands as `//>` lines of a temporary Move script
+        let temp_file = NamedTempFile::new().map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+        {
+            let mut file = File::create(temp_file.path()).map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+            let txn_script = "\n".repeat(33) + "//> " + &commands.join(";\n//> ");
+            file.write_all(txn_script.as_bytes()).map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+            file.flush().map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+        }
+
+        let command_text = "run".to_string();
+        let task_text = "//#".to_owned() + &command_text.replace('\n', "\n//#");
+        let arg_ptb = TaskInput {
+            command: SuiSubcommand::ProgrammableTransaction(ProgrammableTransactionCommand { 
+                sender: Some(sender.clone()), 
+                sponsor: None,
+                gas_budget: Some(gas_budget.unwrap_or(5_000_000_000)), 
+                gas_price: Some(1000),
+                gas_payment: None,
+                dev_inspect: false,
+                dry_run,
+                inputs,
+            }),
+            name: "programmable".to_string(),
+            number: 0,
+            start_line: 1,
+            command_lines_stop: 1,
+            stop_line: 1,
+            data: Some(temp_file),
+            task_text,
+        };
+
+        // Execute the programmable transaction
+        match self.adapter.handle_subcommand(arg_ptb).await {
+            Ok(out) => {
+                info!("programmable transaction succeeded");
+                debug!(output = ?out, "programmable transaction output");
+                if !dry_run {
+                    if let Some(output) = out.as_deref() {
+                        self.record_output(output);
+                        self.name_created_objects(output).await;
+                    }
+                }
+                self.record_operation(operation, Ok(out.clone()));
+                Ok(out)
+            }
+            Err(err) => {
+                warn!(error = ?err, "programmable transaction failed");
+                self.record_operation(operation, Err(err.to_string()));
+                Err(self.abort_error(err.into()))
+            }
+        }
+    }
+
+    // Run a task parsed from a transactional test file, see `transactional::run_file`.
+    // The session is initialized already, so `init` tasks are refused.
+    #[instrument(name = "task", skip_all, fields(task = %task.name, line = task.start_line))]
+    pub async fn run_task(
+        &mut self,
+        task: TaskInput<transactional::SuiTaskCommand>
+    ) -> Result<Option<String>, Box<dyn error::Error>> {
+        if let TaskCommand::Init(..) = task.command {
+            return Err("The session is already initialized".into());
+        }
+
+        let data = match task.data.as_ref() {
+            Some(file) => Some(std::fs::read_to_string(file.path())?),
+            None => None,
+        };
+        let operation = transcript::Operation::Task {
+            text: transactional::task_source(&task.task_text, data.as_deref()),
+        };
+        let is_publish = matches!(task.command, TaskCommand::Publish(..));
+        let named_before = self.adapter.compiled_state().named_address_mapping.clone();
+        match self.adapter.handle_command(task).await {
+            Ok(out) => {
+                info!("task succeeded");
+                debug!(output = ?out, "task output");
+                if let Some(output) = out.as_deref() {
+                    self.record_output(output);
+                    self.name_created_objects(output).await;
+                }
+
+                // Named addresses bound by the publish are the new packages. Their modules
+                // are registered for abort decoding like `publish_compiled_module` does,
+                // except for source maps, which the adapter does not hand back for tasks.
+                if is_publish {
+                    let published: Vec<(String, AccountAddress)> = self.adapter
+                        .compiled_state()
+                        .named_address_mapping
+                        .iter()
+                        .filter(|(name, addr)| named_before.get(*name) != Some(*addr))
+                        .map(|(name, addr)| (name.clone(), addr.into_inner()))
+                        .collect();
+                    for (name, address) in published {
+                        let modules: Vec<CompiledModule> = self.adapter
+                            .compiled_state()
+                            .dep_modules()
+                            .filter(|module| *module.self_id().address() == address)
+                            .cloned()
+                            .collect();
+                        self.package_map.insert(name, address);
+                        self.module_map.insert(address, modules);
+                    }
+                }
+                self.record_operation(operation, Ok(out.clone()));
+                Ok(out)
+            }
+            Err(err) => {
+                warn!(error = ?err, "task failed");
+                self.record_operation(operation, Err(err.to_string()));
+                Err(err.into())
+            }
+        }
+    }
+
+    // Whether `name` is a named address of the session, package or account
+    pub fn has_named_address(
+        &self,
+        name: &str
+    ) -> bool {
+        self.adapter.compiled_state().named_address_mapping.contains_key(name)
+    }
+
+    // Address bound to the named address `name`, `0x0` for placeholders not yet published
+    pub fn get_named_address(
+        &self,
+        name: &str
+    ) -> Option<AccountAddress> {
+        self.adapter.compiled_state().named_address_mapping.get(name).map(|addr| addr.into_inner())
+    }
+
     pub fn get_account_address(
         &self, 
         account_name: &str
@@ -465,4 +835,330 @@ impl SuiTF {
     ) -> Option<AccountAddress> {
         self.package_map.get(package_name).cloned()
     }
-}
\ No newline at end of file
+
+    pub fn get_known_objects(&self) -> Vec<FakeID> {
+        self.known_objects
+            .iter()
+            .map(|&(task, idx)| FakeID::Enumerated(task, idx))
+            .collect()
+    }
+
+    pub fn get_events(&self) -> &[output::EventRecord] {
+        &self.events
+    }
+
+    // Net gas charged by all transactions so far
+    pub fn get_gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    pub fn get_account_name(
+        &self,
+        account_address: &AccountAddress
+    ) -> Option<String> {
+        self.account_map.get(account_address).cloned()
+    }
+
+    pub fn get_package_name(
+        &self,
+        package_addr: &AccountAddress
+    ) -> Option<String> {
+        self.package_map.iter().find_map(|(name, addr)| {
+            if addr == package_addr {
+                Some(name.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    // Total balance of the `Coin<coin_type>` objects owned by an account, among the objects seen so far
+    pub async fn get_balance(
+        &mut self,
+        owner: AccountAddress,
+        coin_type: &str
+    ) -> Result<u128, Box<dyn error::Error>> {
+        let owner_hex = owner.to_hex_literal();
+        let owner_name = self.get_account_name(&owner);
+        let mut total: u128 = 0;
+
+        for id in self.get_known_objects() {
+            // Deleted and wrapped objects can no longer be viewed
+            let raw = match self.lookup_object(id).await {
+                Ok(Some(raw)) => raw,
+                _ => continue,
+            };
+
+            let is_coin = output::parse_object_type(&raw)
+                .as_deref()
+                .and_then(output::coin_inner_type)
+                .map(|inner| output::type_matches(self, inner, coin_type))
+                .unwrap_or(false);
+            if !is_coin {
+                continue;
+            }
+
+            let owned = output::parse_owner_account(&raw)
+                .map(|addr| addr == owner_hex || Some(&addr) == owner_name.as_ref())
+                .unwrap_or(false);
+            if !owned {
+                continue;
+            }
+
+            let balance = Self::parse_output(&raw)
+                .pointer("/Contents/balance/value")
+                .and_then(|v| v.as_str())
+                .and_then(|v| v.trim_end_matches(',').parse::<u128>().ok())
+                .unwrap_or(0);
+            total += balance;
+        }
+
+        Ok(total)
+    }
+
+    fn record_output(
+        &mut self,
+        output: &str
+    ) {
+        self.known_objects.extend(output::parse_object_refs(output));
+        // The operation is recorded right after its output
+        let transaction = self.history.len() as u64;
+        self.events.extend(output::parse_events(output).into_iter().map(|mut event| {
+            event.transaction = transaction;
+            event
+        }));
+        self.gas_used += output::parse_gas_used(output).unwrap_or(0);
+    }
+
+    // Name an object, replacing any object of the same name
+    pub fn alias(
+        &mut self,
+        name: &str,
+        id: FakeID
+    ) -> Result<(), Box<dyn error::Error>> {
+        if !alias::is_valid_alias(name) {
+            return Err(format!("Bad alias '{}', expected a name such as `pool` or `admin_cap`", name).into());
+        }
+        self.aliases.insert(name.to_string(), id);
+        Ok(())
+    }
+
+    // Name the object matching `rule` once it is created, see `alias::AliasRule`
+    pub fn auto_alias(
+        &mut self,
+        rule: alias::AliasRule
+    ) -> Result<(), Box<dyn error::Error>> {
+        if !alias::is_valid_alias(&rule.name) {
+            return Err(format!("Bad alias '{}', expected a name such as `pool` or `admin_cap`", rule.name).into());
+        }
+        self.alias_rules.push(rule);
+        Ok(())
+    }
+
+    pub fn get_aliases(&self) -> &BTreeMap<String, FakeID> {
+        &self.aliases
+    }
+
+    // Name of an object, when it has one
+    pub fn alias_of(
+        &self,
+        id: FakeID
+    ) -> Option<&str> {
+        self.aliases.iter().find(|(_, aliased)| **aliased == id).map(|(name, _)| name.as_str())
+    }
+
+    pub fn resolve_object(
+        &self,
+        key: impl Into<alias::ObjectKey>
+    ) -> Result<FakeID, Box<dyn error::Error>> {
+        match key.into() {
+            alias::ObjectKey::Id(id) => Ok(id),
+            alias::ObjectKey::Alias(name) => self.aliases
+                .get(&name)
+                .copied()
+                .ok_or_else(|| format!("Unknown object alias '{}'", name).into()),
+        }
+    }
+
+    // Apply the pending alias rules to the objects created by a transaction
+    async fn name_created_objects(
+        &mut self,
+        output: &str
+    ) {
+        if self.alias_rules.is_empty() {
+            return;
+        }
+        let created: Vec<(u64, u64)> = output::parse_object_changes(output)
+            .into_iter()
+            .filter(|(kind, _)| kind == "created")
+            .flat_map(|(_, refs)| refs)
+            .collect();
+
+        for (task, idx) in created {
+            // Each lookup takes a task number, so objects no rule can match are skipped
+            if !self.alias_rules.iter().any(|rule| rule.task.map_or(true, |t| t == task)) {
+                continue;
+            }
+            let id = FakeID::Enumerated(task, idx);
+            let object_type = match self.lookup_object(id).await {
+                Ok(Some(out)) => match output::parse_object_type(&out) {
+                    Some(object_type) => object_type,
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let mut named = Vec::new();
+            let mut rules = std::mem::take(&mut self.alias_rules);
+            rules.retain_mut(|rule| {
+                if rule.observe(self, task, &object_type) {
+                    named.push(rule.name.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            self.alias_rules = rules;
+            for name in named {
+                info!(alias = %name, object = ?id, object_type = %object_type, "named object");
+                self.aliases.insert(name, id);
+            }
+            if self.alias_rules.is_empty() {
+                return;
+            }
+        }
+    }
+
+    // Log operations to a transcript, see `transcript`. Operations already run are
+    // written first, so the transcript always starts with the initialization.
+    pub fn record_transcript(
+        &mut self,
+        mut recorder: transcript::Recorder
+    ) -> Result<(), Box<dyn error::Error>> {
+        for record in self.history.iter() {
+            recorder.record(record)?;
+        }
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) -> Option<transcript::Recorder> {
+        self.recorder.take()
+    }
+
+    // Independent copy of this environment, the template is left untouched whatever the
+    // copy does. Costs a re-run of the whole history, see `fork::Template`.
+    pub async fn fork(&self) -> Result<SuiTF, Box<dyn error::Error>> {
+        fork::Template::new(self).instantiate().await
+    }
+
+    pub fn get_framework(&self) -> &FrameworkSource {
+        &self.framework
+    }
+
+    pub fn get_history(&self) -> &[transcript::Record] {
+        &self.history
+    }
+
+    pub fn init_outcome(&self) -> &transcript::Outcome {
+        &self.history[0].result
+    }
+
+    fn record_operation(
+        &mut self,
+        operation: transcript::Operation,
+        result: Result<Option<String>, String>
+    ) {
+        let record = transcript::Record {
+            seq: self.history.len() as u64,
+            operation,
+            result: transcript::Outcome::from_result(&result),
+        };
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&record) {
+                error!(error = %e, "failed to write transcript");
+            }
+        }
+        self.history.push(record);
+    }
+
+    // Register a source file of a published package, so that aborts in its modules
+    // are shown with the source line, see `package::load_sources`
+    pub fn add_source(
+        &mut self,
+        name: &str,
+        text: String
+    ) {
+        self.sources.insert(FileHash::new(&text), abort::SourceFile { name: name.to_string(), text });
+    }
+
+    // Decode a Move abort from an error returned by a call or a transaction, resolving
+    // the function, constant name and source line from the published modules
+    pub fn decode_abort(
+        &self,
+        error: &str
+    ) -> Option<abort::MoveAbort> {
+        let mut abort = abort::MoveAbort::parse(error)?;
+        let address = abort.address.or_else(|| self.package_map.get(&abort.package).copied());
+        if let Some(address) = address {
+            abort.address = Some(address);
+            let module = self.module_map
+                .get(&address)
+                .and_then(|modules| modules.iter().find(|m| m.name().as_str() == abort.module));
+            if let Some(module) = module {
+                let source_map = self.source_maps.get(&(address, abort.module.clone()));
+                abort.resolve(module, source_map, &self.sources);
+            }
+        }
+        Some(abort)
+    }
+
+    // Aborts are returned as `abort::MoveAbort`, other errors unchanged
+    fn abort_error(
+        &self,
+        err: Box<dyn error::Error>
+    ) -> Box<dyn error::Error> {
+        match self.decode_abort(&err.to_string()) {
+            Some(abort) => Box::new(abort),
+            None => err,
+        }
+    }
+
+    // Evaluate a win condition, giving the reason when it is not met (see `win::Unmet`)
+    pub async fn check_win(
+        &mut self,
+        condition: &win::WinCondition
+    ) -> Result<(), win::Unmet> {
+        condition.evaluate(self).await
+    }
+
+    pub fn get_package_abi(
+        &self,
+        package_addr: AccountAddress
+    ) -> Option<abi::PackageAbi> {
+        self.module_map
+            .get(&package_addr)
+            .map(|modules| abi::PackageAbi::from_modules(package_addr, modules))
+    }
+
+    pub fn get_module_abi(
+        &self,
+        package_addr: AccountAddress,
+        module_name: &str
+    ) -> Option<abi::ModuleAbi> {
+        self.module_map
+            .get(&package_addr)?
+            .iter()
+            .find(|m| m.name().as_str() == module_name)
+            .map(abi::ModuleAbi::from_module)
+    }
+
+    pub fn get_package_modules(
+        &self,
+        package_addr: AccountAddress
+    ) -> Option<Vec<String>> {
+        self.module_map.get(&package_addr).map(|modules| {
+            modules.iter().map(|m| m.name().to_string()).collect()
+        })
+    }
+}
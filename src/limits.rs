@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use crate::SuiTF;
+
+// Resources a single player session may use, `None` meaning unlimited
+#[derive(Debug, Clone, Default)]
+pub struct SessionLimits {
+    pub max_transactions: Option<u64>,
+    pub max_gas: Option<u64>,
+    // The adapter reports no gas for failed transactions, so they are capped on their own
+    pub max_failed_transactions: Option<u64>,
+    pub max_published_modules: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+// Usage of a session against its limits. Only actions taken on behalf of the
+// player are charged, challenge setup and win condition checks are free.
+pub struct SessionMeter {
+    limits: SessionLimits,
+    started: Instant,
+    transactions: u64,
+    failed_transactions: u64,
+    gas_used: u64,
+    published_modules: usize,
+}
+
+impl SessionMeter {
+    pub fn new(limits: SessionLimits) -> Self {
+        SessionMeter {
+            limits,
+            started: Instant::now(),
+            transactions: 0,
+            failed_transactions: 0,
+            gas_used: 0,
+            published_modules: 0,
+        }
+    }
+
+    pub fn limits(&self) -> &SessionLimits {
+        &self.limits
+    }
+
+    pub fn transactions(&self) -> u64 {
+        self.transactions
+    }
+
+    pub fn failed_transactions(&self) -> u64 {
+        self.failed_transactions
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    pub fn published_modules(&self) -> usize {
+        self.published_modules
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Time left before the session expires
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.limits.max_duration.map(|max| max.saturating_sub(self.elapsed()))
+    }
+
+    pub fn check_time(&self) -> Result<(), Box<dyn Error>> {
+        match self.limits.max_duration {
+            Some(max) if self.elapsed() >= max => Err(format!(
+                "Session limit reached: sessions last at most {} seconds",
+                max.as_secs()
+            ).into()),
+            _ => Ok(()),
+        }
+    }
+
+    // Whether the player may send another transaction
+    pub fn check_transaction(&self) -> Result<(), Box<dyn Error>> {
+        self.check_time()?;
+        if let Some(max) = self.limits.max_transactions {
+            if self.transactions >= max {
+                return Err(format!("Session limit reached: at most {} transactions per session", max).into());
+            }
+        }
+        if let Some(max) = self.limits.max_failed_transactions {
+            if self.failed_transactions >= max {
+                return Err(format!("Session limit reached: at most {} failed transactions per session", max).into());
+            }
+        }
+        if let Some(max) = self.limits.max_gas {
+            if self.gas_used >= max {
+                return Err(format!(
+                    "Session limit reached: {} gas used out of {} per session",
+                    self.gas_used,
+                    max
+                ).into());
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the player may publish `module_count` more modules
+    pub fn check_publish(
+        &self,
+        module_count: usize
+    ) -> Result<(), Box<dyn Error>> {
+        self.check_transaction()?;
+        if let Some(max) = self.limits.max_published_modules {
+            if self.published_modules + module_count > max {
+                return Err(format!(
+                    "Session limit reached: at most {} published modules per session, {} already published",
+                    max,
+                    self.published_modules
+                ).into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn charge(
+        &mut self,
+        transactions: u64,
+        gas: u64,
+        modules: usize
+    ) {
+        self.transactions += transactions;
+        self.gas_used += gas;
+        self.published_modules += modules;
+    }
+
+    // Charge one transaction attempt and the gas `suitf` reports since `gas_before`.
+    // Failed transactions count against the transaction limit too, and against
+    // `max_failed_transactions` as their gas is not reported; their modules are not charged.
+    pub fn charge_transaction(
+        &mut self,
+        suitf: &SuiTF,
+        gas_before: u64,
+        modules: usize,
+        succeeded: bool
+    ) {
+        if !succeeded {
+            self.failed_transactions += 1;
+        }
+        self.charge(1, suitf.get_gas_used().saturating_sub(gas_before), if succeeded { modules } else { 0 });
+    }
+}
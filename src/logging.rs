@@ -0,0 +1,44 @@
+use std::error::Error;
+
+use tracing_subscriber::EnvFilter;
+
+// The framework logs through `tracing`: a `session` span per connection, and a span per
+// transaction (`publish`, `call`, `ptb`, `fund`, `view`, `task`) carrying its package,
+// function, sender and so on. The solver side (`client::Client`) logs its connection,
+// uploads and the server's plain text output the same way. Results are `info`, failures `warn`, and full adapter
+// outputs `debug`. Nothing is printed until a subscriber is installed, either one of
+// the application's own or one of the helpers below.
+//
+// The filter can be overridden with `SUI_CTF_LOG`, in `EnvFilter` syntax:
+//
+//   SUI_CTF_LOG=debug                           everything, including adapter outputs
+//   SUI_CTF_LOG=warn                            failures only
+//   SUI_CTF_LOG=warn,sui_ctf_framework=debug    debug output of the framework alone
+pub const LOG_ENV: &str = "SUI_CTF_LOG";
+
+// Framework events at `info`, the Sui crates underneath log a lot on their own
+pub const DEFAULT_FILTER: &str = "warn,sui_ctf_framework=info";
+
+// Log framework events at `info` and above to stderr
+pub fn init() -> Result<(), Box<dyn Error>> {
+    init_with(DEFAULT_FILTER)
+}
+
+// Log errors only, e.g. for servers under load
+pub fn init_quiet() -> Result<(), Box<dyn Error>> {
+    init_with("error")
+}
+
+// Log with `EnvFilter` directives such as `debug` or `warn,sui_ctf_framework=debug`,
+// unless `SUI_CTF_LOG` is set
+pub fn init_with(filter: &str) -> Result<(), Box<dyn Error>> {
+    let directives = std::env::var(LOG_ENV).unwrap_or_else(|_| filter.to_string());
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|e| format!("Invalid log filter '{}': {}", directives, e))?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .try_init()
+        .map_err(|e| format!("Failed to install the log subscriber: {}", e).into())
+}
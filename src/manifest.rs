@@ -0,0 +1,605 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use move_compiler::shared::NumericalAddress;
+use move_core_types::account_address::AccountAddress;
+
+use crate::{
+    alias::AliasRule,
+    jsonl,
+    limits::SessionLimits,
+    package,
+    pool::PoolConfig,
+    server::{Challenge, ChallengeServer, PlayerErrors, ServerConfig, Session},
+    transactional::{self, TaskStatus},
+    win::WinCondition,
+    FrameworkSource,
+    SuiTF,
+};
+
+// Declarative challenge description, usually a `challenge.toml` next to the challenge:
+//
+//   [challenge]
+//   name = "mile-high-city"
+//
+//   [addresses]
+//   challenge = "0x0"
+//   solution = "0x0"
+//
+//   [[publish]]
+//   build_dir = "./chall/build"
+//   package = "challenge"
+//
+//   [solution]
+//   call = { package = "solution", function = "solve", args = [{ object = [1, 1] }] }
+//
+//   [check]
+//   package = "challenge"
+//   function = "check_status"
+//   args = [{ object = [1, 1] }]
+//
+//   [win]
+//   kind = "owns_object"
+//   owner = "solver"
+//   object_type = "challenge::flag::Flag"
+//
+// The player wins when the `check` call succeeds and the `win` condition holds,
+// either one can be left out. See `win::WinCondition` for the conditions.
+// Call arguments use the JSON-lines argument syntax, see `jsonl::parse_value`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub challenge: ChallengeInfo,
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+    #[serde(default)]
+    pub publish: Vec<PublishSpec>,
+    #[serde(default)]
+    pub fund: Vec<FundSpec>,
+    #[serde(default)]
+    pub setup: Vec<CallSpec>,
+    // Objects named as they are created, usable as `{ object = "pool" }`
+    #[serde(default)]
+    pub aliases: Vec<AliasSpec>,
+    // Transactional test files run before the publish, fund and setup steps, see
+    // `transactional::run_file`
+    #[serde(default)]
+    pub setup_files: Vec<PathBuf>,
+    #[serde(default)]
+    pub solution: SolutionSpec,
+    pub check: Option<CallSpec>,
+    pub win: Option<WinCondition>,
+    #[serde(default)]
+    pub limits: LimitsSpec,
+    // Pre-warmed environments, see `pool`
+    pub pool: Option<PoolSpec>,
+    // Relative paths are resolved against the manifest's directory
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChallengeInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub bind: Option<String>,
+    #[serde(default = "default_accounts")]
+    pub accounts: Vec<String>,
+    pub pow_difficulty: Option<u32>,
+    pub max_upload_size: Option<usize>,
+    pub transcript_dir: Option<PathBuf>,
+    // `full`, `aborts` or `hidden`, see `server::PlayerErrors`
+    pub player_errors: Option<PlayerErrors>,
+    pub max_sessions: Option<usize>,
+    // Local Sui framework packages to compile against, see `FrameworkSource`
+    pub framework_dir: Option<PathBuf>,
+}
+
+// A package built with `sui move build`, published during setup
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublishSpec {
+    pub build_dir: PathBuf,
+    pub package: String,
+    pub named_address: Option<String>,
+    #[serde(default = "default_setup_sender")]
+    pub sender: String,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FundSpec {
+    pub account: String,
+    pub amount: u64,
+    #[serde(default = "default_setup_sender")]
+    pub sender: String,
+}
+
+// Names the `nth` object of `object_type` created, by `task` when given, see `alias::AliasRule`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AliasSpec {
+    pub name: String,
+    pub object_type: String,
+    pub task: Option<u64>,
+    pub nth: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CallSpec {
+    // Package name or address
+    pub package: String,
+    // Found from the package ABI when left out
+    pub module: Option<String>,
+    pub function: String,
+    #[serde(default)]
+    pub args: Vec<Value>,
+    #[serde(default)]
+    pub type_args: Vec<String>,
+    pub sender: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SolutionMode {
+    // The player uploads a package, then `call` runs it
+    #[default]
+    Upload,
+    // The player drives the session with JSON-lines commands
+    Interactive,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SolutionSpec {
+    #[serde(default)]
+    pub mode: SolutionMode,
+    #[serde(default = "default_solution_address")]
+    pub named_address: String,
+    // Packages the solution may depend on, every published package when empty
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    pub call: Option<CallSpec>,
+}
+
+impl Default for SolutionSpec {
+    fn default() -> Self {
+        SolutionSpec {
+            mode: SolutionMode::default(),
+            named_address: default_solution_address(),
+            dependencies: Vec::new(),
+            call: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LimitsSpec {
+    pub max_transactions: Option<u64>,
+    pub max_gas: Option<u64>,
+    pub max_failed_transactions: Option<u64>,
+    pub max_published_modules: Option<usize>,
+    pub max_duration_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PoolSpec {
+    pub size: Option<usize>,
+    pub max_age_secs: Option<u64>,
+}
+
+fn default_accounts() -> Vec<String> {
+    vec!["challenger".to_string(), "solver".to_string()]
+}
+
+fn default_setup_sender() -> String {
+    "challenger".to_string()
+}
+
+fn default_solution_address() -> String {
+    "solution".to_string()
+}
+
+impl PublishSpec {
+    pub fn named_address(&self) -> &str {
+        self.named_address.as_deref().unwrap_or(&self.package)
+    }
+}
+
+impl CallSpec {
+    pub fn new(
+        package: &str,
+        function: &str
+    ) -> Self {
+        CallSpec {
+            package: package.to_string(),
+            module: None,
+            function: function.to_string(),
+            args: Vec::new(),
+            type_args: Vec::new(),
+            sender: None,
+        }
+    }
+
+    pub fn module(
+        mut self,
+        module: &str
+    ) -> Self {
+        self.module = Some(module.to_string());
+        self
+    }
+
+    // Arguments in the JSON-lines syntax, e.g. `json!({ "object": [1, 1] })`
+    pub fn args(
+        mut self,
+        args: Vec<Value>
+    ) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn type_args(
+        mut self,
+        type_args: Vec<String>
+    ) -> Self {
+        self.type_args = type_args;
+        self
+    }
+
+    pub fn sender(
+        mut self,
+        sender: &str
+    ) -> Self {
+        self.sender = Some(sender.to_string());
+        self
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut manifest = Manifest::parse(&text)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?;
+        manifest.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(manifest)
+    }
+
+    pub fn parse(text: &str) -> Result<Manifest, Box<dyn Error>> {
+        let manifest: Manifest = toml::from_str(text)?;
+        manifest.named_addresses()?;
+        Ok(manifest)
+    }
+
+    pub fn named_addresses(&self) -> Result<Vec<(String, NumericalAddress)>, Box<dyn Error>> {
+        let mut named_addresses = Vec::new();
+        for (name, addr) in self.addresses.iter() {
+            let addr = NumericalAddress::parse_str(addr)
+                .map_err(|e| format!("Bad address for '{}': {}", name, e))?;
+            named_addresses.push((name.clone(), addr));
+        }
+
+        // Packages and the solution need a named address to be published under
+        let mut required: Vec<&str> = self.publish.iter().map(|p| p.named_address()).collect();
+        required.push(&self.solution.named_address);
+        for name in required {
+            if !self.addresses.contains_key(name) {
+                named_addresses.push((name.to_string(), NumericalAddress::parse_str("0x0")?));
+            }
+        }
+
+        Ok(named_addresses)
+    }
+
+    pub fn limits(&self) -> SessionLimits {
+        SessionLimits {
+            max_transactions: self.limits.max_transactions,
+            max_gas: self.limits.max_gas,
+            max_failed_transactions: self.limits.max_failed_transactions,
+            max_published_modules: self.limits.max_published_modules,
+            max_duration: self.limits.max_duration_secs.map(Duration::from_secs),
+        }
+    }
+
+    pub fn server_config(&self) -> Result<ServerConfig, Box<dyn Error>> {
+        let mut config = ServerConfig {
+            named_addresses: self.named_addresses()?,
+            accounts: self.challenge.accounts.clone(),
+            pow_difficulty: self.challenge.pow_difficulty,
+            limits: self.limits(),
+            framework: self.framework(),
+            ..Default::default()
+        };
+        if let Some(bind) = &self.challenge.bind {
+            config.bind_addr = bind.clone();
+        }
+        if let Some(max_upload_size) = self.challenge.max_upload_size {
+            config.max_upload_size = max_upload_size;
+        }
+        if let Some(dir) = &self.challenge.transcript_dir {
+            config.transcript_dir = Some(self.base_dir.join(dir));
+        }
+        if let Some(pool) = &self.pool {
+            let default = PoolConfig::default();
+            config.pool = Some(PoolConfig {
+                size: pool.size.unwrap_or(default.size),
+                max_age: pool.max_age_secs.map(Duration::from_secs).unwrap_or(default.max_age),
+            });
+        }
+        if let Some(max_sessions) = self.challenge.max_sessions {
+            config.max_sessions = max_sessions;
+        }
+        if let Some(player_errors) = self.challenge.player_errors {
+            config.player_errors = player_errors;
+        }
+        Ok(config)
+    }
+
+    // Manifest for a bare `sui move build` output (`<build_dir>/<package>`), published as
+    // `challenge`. It has no win condition, set `check` or `win` before serving it.
+    pub fn from_build_dir(package_dir: &Path) -> Result<Manifest, Box<dyn Error>> {
+        let (build_dir, package) = split_package_dir(package_dir)?;
+        let mut manifest = Manifest::parse(&format!("[challenge]\nname = \"{}\"\n", package))?;
+        manifest.publish.push(PublishSpec {
+            build_dir,
+            package,
+            named_address: Some("challenge".to_string()),
+            sender: default_setup_sender(),
+            dependencies: Vec::new(),
+        });
+        Ok(manifest)
+    }
+
+    // Fresh environment with the manifest's named addresses and accounts
+    pub async fn initialize(&self) -> Result<SuiTF, Box<dyn Error>> {
+        SuiTF::initialize_with_framework(
+            self.named_addresses()?,
+            Some(self.challenge.accounts.clone()),
+            &self.framework(),
+        ).await
+    }
+
+    pub fn framework(&self) -> FrameworkSource {
+        match &self.challenge.framework_dir {
+            Some(dir) => FrameworkSource::Local(self.base_dir.join(dir)),
+            None => FrameworkSource::Bundled,
+        }
+    }
+
+    // Run the setup files, then publish, fund and run the setup calls, giving the address
+    // of each published package. Files come first so that a prototype file can set up
+    // the accounts and objects the manifest's steps build on.
+    pub async fn setup(
+        &self,
+        suitf: &mut SuiTF
+    ) -> Result<Vec<(String, AccountAddress)>, Box<dyn Error>> {
+        for spec in self.aliases.iter() {
+            let mut rule = AliasRule::new(&spec.name, &spec.object_type).nth(spec.nth.unwrap_or(1));
+            if let Some(task) = spec.task {
+                rule = rule.task(task);
+            }
+            suitf.auto_alias(rule)?;
+        }
+
+        for file in self.setup_files.iter() {
+            let path = self.base_dir.join(file);
+            let results = transactional::run_file(suitf, &path).await?;
+            if let Some(failed) = results.iter().find(|r| r.status == TaskStatus::Failed) {
+                return Err(format!(
+                    "Task {} at {}:{} failed: {}",
+                    failed.name,
+                    path.display(),
+                    failed.line,
+                    failed.error.as_deref().unwrap_or_default()
+                ).into());
+            }
+        }
+
+        let mut published = Vec::new();
+        for publish in self.publish.iter() {
+            let build_dir = self.base_dir.join(&publish.build_dir);
+            let modules = package::load_package(&build_dir, &publish.package, publish.named_address())?;
+            for (name, text) in package::load_sources(&build_dir, &publish.package)? {
+                suitf.add_source(&name, text);
+            }
+            let addr = suitf.publish_compiled_module(
+                modules,
+                publish.dependencies.clone(),
+                Some(publish.sender.clone()),
+            ).await?;
+            info!(package = %publish.named_address(), address = %addr, "published setup package");
+            published.push((publish.named_address().to_string(), addr));
+        }
+
+        for fund in self.fund.iter() {
+            let sender = suitf
+                .get_account_address(&fund.sender)
+                .ok_or_else(|| format!("Unknown account '{}'", fund.sender))?;
+            suitf.fund_account(fund.account.clone(), fund.amount, sender).await?;
+        }
+
+        for call in self.setup.iter() {
+            run_call(suitf, call, "challenger").await
+                .map_err(|e| format!("Setup call {}::{} failed: {}", call.package, call.function, e))?;
+        }
+
+        Ok(published)
+    }
+
+    // Publish a built solution package (`<build_dir>/<package>`) as `solver` and run
+    // `solution.call`, without a connected player
+    pub async fn run_solution(
+        &self,
+        suitf: &mut SuiTF,
+        package_dir: &Path
+    ) -> Result<AccountAddress, Box<dyn Error>> {
+        let (build_dir, package) = split_package_dir(package_dir)?;
+        let modules = package::load_package(&build_dir, &package, &self.solution.named_address)?;
+        for (name, text) in package::load_sources(&build_dir, &package)? {
+            suitf.add_source(&name, text);
+        }
+        let addr = suitf.publish_compiled_module(
+            modules,
+            self.solution_dependencies(),
+            Some("solver".to_string()),
+        ).await?;
+        info!(address = %addr, "published solution");
+
+        if let Some(call) = &self.solution.call {
+            let output = run_call(suitf, call, "solver").await?;
+            debug!(output = ?output, "solution call output");
+        }
+        Ok(addr)
+    }
+
+    // `Ok` when the `check` call succeeds and the `win` condition holds, otherwise the reason
+    pub async fn check_solved(
+        &self,
+        suitf: &mut SuiTF
+    ) -> Result<(), String> {
+        if self.check.is_none() && self.win.is_none() {
+            return Err("The manifest has no win condition".to_string());
+        }
+
+        if let Some(call) = &self.check {
+            run_call(suitf, call, "solver").await
+                .map_err(|e| format!("Check {}::{} failed: {}", call.package, call.function, e))?;
+        }
+        if let Some(win) = &self.win {
+            suitf.check_win(win).await
+                .map_err(|reason| format!("Win condition not met: {}", reason))?;
+        }
+        Ok(())
+    }
+
+    // Packages the solution may depend on
+    pub fn solution_dependencies(&self) -> Vec<String> {
+        if self.solution.dependencies.is_empty() {
+            self.publish.iter().map(|p| p.named_address().to_string()).collect()
+        } else {
+            self.solution.dependencies.clone()
+        }
+    }
+}
+
+// `./solve/build/solution` -> (`./solve/build`, `solution`)
+fn split_package_dir(package_dir: &Path) -> Result<(PathBuf, String), Box<dyn Error>> {
+    let package = package_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Bad package directory {}", package_dir.display()))?;
+    let build_dir = package_dir.parent().map(Path::to_path_buf).unwrap_or_default();
+    Ok((build_dir, package.to_string()))
+}
+
+// Run a manifest call, as `default_sender` unless the call names its sender
+pub async fn run_call(
+    suitf: &mut SuiTF,
+    call: &CallSpec,
+    default_sender: &str
+) -> Result<Option<String>, Box<dyn Error>> {
+    let package = jsonl::parse_address(suitf, &call.package)?;
+    let module = match &call.module {
+        Some(module) => module.clone(),
+        None => jsonl::find_module(suitf, package, &call.function)?,
+    };
+    let args = jsonl::parse_args(suitf, &call.args)?;
+    let mut type_args = Vec::new();
+    for t in call.type_args.iter() {
+        type_args.push(jsonl::parse_type_tag(suitf, t)?);
+    }
+
+    suitf.call_function(
+        package,
+        &module,
+        &call.function,
+        args,
+        type_args,
+        Some(call.sender.clone().unwrap_or_else(|| default_sender.to_string())),
+    ).await
+}
+
+// `Challenge` implementation driven by a manifest
+pub struct ManifestChallenge {
+    manifest: Manifest,
+}
+
+impl ManifestChallenge {
+    pub fn new(manifest: Manifest) -> Self {
+        ManifestChallenge { manifest }
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+}
+
+impl Challenge for ManifestChallenge {
+    async fn prepare(&self, suitf: &mut SuiTF) -> Result<(), Box<dyn Error>> {
+        self.manifest.setup(suitf).await?;
+        Ok(())
+    }
+
+    async fn setup(&self, session: &mut Session) -> Result<(), Box<dyn Error>> {
+        for publish in self.manifest.publish.iter() {
+            if let Some(addr) = session.suitf.get_package_address(publish.named_address()) {
+                session.send(&format!("[SERVER] Challenge modules published at: {}\n", addr))?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn solve(&self, session: &mut Session) -> Result<(), Box<dyn Error>> {
+        let solution = &self.manifest.solution;
+        let dependencies = self.manifest.solution_dependencies();
+
+        match solution.mode {
+            SolutionMode::Upload => {
+                session.publish_solution_package(&solution.named_address, &dependencies).await?;
+                if let Some(call) = &solution.call {
+                    let output = run_call(&mut session.suitf, call, "solver").await?;
+                    debug!(output = ?output, "solution call output");
+                }
+                Ok(())
+            }
+            SolutionMode::Interactive => {
+                let config = jsonl::CommandConfig {
+                    player: "solver".to_string(),
+                    default_named_address: solution.named_address.clone(),
+                    allowed_dependencies: dependencies,
+                    ..session.command_config()
+                };
+                session.serve_commands(self, &config).await
+            }
+        }
+    }
+
+    async fn check(&self, session: &mut Session) -> Result<bool, Box<dyn Error>> {
+        if self.manifest.check.is_none() && self.manifest.win.is_none() {
+            return Err("The manifest has no win condition".into());
+        }
+        match self.manifest.check_solved(&mut session.suitf).await {
+            Ok(()) => Ok(true),
+            Err(reason) => {
+                info!(reason = %reason, "not solved");
+                Ok(false)
+            }
+        }
+    }
+}
+
+// Load a manifest and serve its challenge, blocking like `ChallengeServer::run`
+pub fn run(path: &Path) -> Result<(), Box<dyn Error>> {
+    let manifest = Manifest::load(path)?;
+    info!(challenge = %manifest.challenge.name, path = %path.display(), "loaded challenge");
+    serve(manifest)
+}
+
+pub fn serve(manifest: Manifest) -> Result<(), Box<dyn Error>> {
+    let config = manifest.server_config()?;
+    ChallengeServer::new(ManifestChallenge::new(manifest), config).run()
+}
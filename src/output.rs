@@ -0,0 +1,310 @@
+use serde::Serialize;
+
+use move_core_types::account_address::AccountAddress;
+
+use crate::{jsonl, SuiTF};
+
+// Event emitted by a transaction, as reported in the adapter's effects summary
+#[derive(Debug, Clone, Serialize)]
+pub struct EventRecord {
+    pub package_id: String,
+    pub transaction_module: String,
+    pub sender: String,
+    pub type_: String,
+    pub contents: Vec<u8>,
+    // Sequence number of the transaction that emitted it, see `SuiTF::get_history`
+    pub transaction: u64,
+}
+
+// Collect every `object(x,y)` enumeration mentioned in an adapter output
+pub fn parse_object_refs(output: &str) -> Vec<(u64, u64)> {
+    let mut refs = Vec::new();
+    let mut rest = output;
+
+    while let Some(start) = rest.find("object(") {
+        rest = &rest[start + "object(".len()..];
+        let end = match rest.find(')') {
+            Some(end) => end,
+            None => break,
+        };
+        if let Some((task, idx)) = rest[..end].split_once(',') {
+            if let (Ok(task), Ok(idx)) = (task.trim().parse(), idx.trim().parse()) {
+                refs.push((task, idx));
+            }
+        }
+        rest = &rest[end..];
+    }
+
+    refs
+}
+
+// Extract the `events: Event { ... }, Event { ... }` entries of an adapter output
+pub fn parse_events(output: &str) -> Vec<EventRecord> {
+    let mut events = Vec::new();
+
+    for line in output.lines() {
+        let line = match line.trim().strip_prefix("events:") {
+            Some(line) => line,
+            None => continue,
+        };
+
+        let mut rest = line;
+        while let Some(start) = rest.find("Event {") {
+            let body_start = start + "Event {".len();
+            let body_len = match matching_brace(&rest[body_start..]) {
+                Some(len) => len,
+                None => break,
+            };
+            let body = &rest[body_start..body_start + body_len];
+            if let Some(event) = parse_event_body(body) {
+                events.push(event);
+            }
+            rest = &rest[body_start + body_len..];
+        }
+    }
+
+    events
+}
+
+// Object type reported on the `Contents: <type> {` line of a view-object output
+pub fn parse_object_type(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let contents = line.trim().strip_prefix("Contents:")?;
+        let type_ = contents.trim().trim_end_matches('{').trim();
+        if type_.is_empty() {
+            None
+        } else {
+            Some(type_.to_string())
+        }
+    })
+}
+
+// Owner reported on the `Owner: ...` line of a view-object output
+pub fn parse_object_owner(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        line.trim().strip_prefix("Owner:").map(|owner| owner.trim().to_string())
+    })
+}
+
+// Address or account name of the owner of an address-owned object
+pub fn parse_owner_account(output: &str) -> Option<String> {
+    parse_object_owner(output)?
+        .strip_prefix("Account Address")
+        .map(|rest| rest.trim().trim_start_matches('(').trim_end_matches(')').trim().to_string())
+}
+
+// Version reported on the `Version: ...` line of a view-object output
+pub fn parse_object_version(output: &str) -> Option<u64> {
+    output.lines().find_map(|line| {
+        line.trim().strip_prefix("Version:")?.trim().parse().ok()
+    })
+}
+
+// Object changes listed in an effects summary, keyed by kind (`created`, `mutated`, ...)
+pub fn parse_object_changes(output: &str) -> Vec<(String, Vec<(u64, u64)>)> {
+    const KINDS: [&str; 6] = ["created", "mutated", "unwrapped", "deleted", "unwrapped_then_deleted", "wrapped"];
+
+    output.lines().filter_map(|line| {
+        let (kind, rest) = line.trim().split_once(": ")?;
+        if KINDS.contains(&kind) {
+            Some((kind.to_string(), parse_object_refs(rest)))
+        } else {
+            None
+        }
+    }).collect()
+}
+
+// Net gas charged by a transaction, from the `gas summary: computation_cost: ...` line
+pub fn parse_gas_used(output: &str) -> Option<u64> {
+    let summary = output.lines().find_map(|line| line.trim().strip_prefix("gas summary:"))?;
+    let cost = |name: &str| -> u64 {
+        summary
+            .split(',')
+            .find_map(|part| part.trim().strip_prefix(name)?.trim().strip_prefix(':')?.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    Some((cost("computation_cost") + cost("storage_cost")).saturating_sub(cost("storage_rebate")))
+}
+
+// Compare types by their full `address::module::Name<...>`. The adapter prints
+// addresses by name (`sui::coin::Coin`) or as `fake(x,y)`, so both sides are
+// normalized first (see `jsonl::normalize_type`).
+pub fn type_matches(
+    suitf: &SuiTF,
+    actual: &str,
+    expected: &str
+) -> bool {
+    jsonl::normalize_type(suitf, actual) == jsonl::normalize_type(suitf, expected)
+}
+
+// `T` of a `0x2::coin::Coin<T>`, coins of other packages are not coins
+pub fn coin_inner_type(type_: &str) -> Option<&str> {
+    let (head, inner) = type_.trim().split_once("::coin::Coin<")?;
+    let is_framework = head == "sui"
+        || AccountAddress::from_hex_literal(head).map_or(false, |addr| addr == AccountAddress::TWO);
+    if !is_framework {
+        return None;
+    }
+    inner.strip_suffix('>')
+}
+
+fn matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn field_value<'a>(
+    body: &'a str,
+    field: &str
+) -> Option<&'a str> {
+    let start = body.find(&format!("{}: ", field))? + field.len() + 2;
+    let rest = &body[start..];
+    let end = rest.find(", ").unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+fn identifier_value(value: &str) -> String {
+    value
+        .trim_start_matches("Identifier(")
+        .trim_end_matches(')')
+        .trim_matches('"')
+        .to_string()
+}
+
+fn parse_event_body(body: &str) -> Option<EventRecord> {
+    let package_id = field_value(body, "package_id")?.to_string();
+    let transaction_module = identifier_value(field_value(body, "transaction_module")?);
+    let sender = field_value(body, "sender")?.to_string();
+
+    let tag_start = body.find("type_: StructTag {")? + "type_: StructTag {".len();
+    let tag_len = matching_brace(&body[tag_start..])?;
+    let tag = &body[tag_start..tag_start + tag_len];
+    let type_ = format!(
+        "{}::{}::{}",
+        field_value(tag, "address")?,
+        identifier_value(field_value(tag, "module")?),
+        identifier_value(field_value(tag, "name")?)
+    );
+
+    let contents_start = body.find("contents: [")? + "contents: [".len();
+    let contents_len = body[contents_start..].find(']')?;
+    let contents = body[contents_start..contents_start + contents_len]
+        .split(',')
+        .filter_map(|b| b.trim().parse::<u8>().ok())
+        .collect();
+
+    Some(EventRecord {
+        package_id,
+        transaction_module,
+        sender,
+        type_,
+        contents,
+        transaction: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Effects of a `run` task as the adapter prints them
+    const EFFECTS: &str = "\
+created: object(2,0), object(2,1)
+mutated: object(0,0), object(1,1)
+deleted: object(1,3)
+gas summary: computation_cost: 1000000, storage_cost: 2964000,  storage_rebate: 978120, non_refundable_storage_fee: 9880";
+
+    const EVENTS: &str = "\
+events: Event { package_id: challenge, transaction_module: Identifier(\"pool\"), sender: solver, type_: StructTag { address: challenge, module: Identifier(\"pool\"), name: Identifier(\"Swapped\"), type_params: [] }, contents: [1, 0, 0, 0, 0, 0, 0, 0] }, Event { package_id: challenge, transaction_module: Identifier(\"pool\"), sender: solver, type_: StructTag { address: sui, module: Identifier(\"coin\"), name: Identifier(\"CoinMetadata\"), type_params: [Struct(StructTag { address: sui, module: Identifier(\"sui\"), name: Identifier(\"SUI\"), type_params: [] })] }, contents: [] }
+created: object(3,0)
+gas summary: computation_cost: 1000000, storage_cost: 988000,  storage_rebate: 0, non_refundable_storage_fee: 0";
+
+    // A `view-object` of a coin
+    const VIEW: &str = "\
+Owner: Account Address ( solver )
+Version: 3
+Contents: sui::coin::Coin<sui::sui::SUI> {
+    id: sui::object::UID {
+        id: sui::object::ID {
+            bytes: fake(2,0),
+        },
+    },
+    balance: sui::balance::Balance<sui::sui::SUI> {
+        value: 1000u64,
+    },
+}";
+
+    #[test]
+    fn parses_object_changes() {
+        assert_eq!(
+            parse_object_changes(EFFECTS),
+            vec![
+                ("created".to_string(), vec![(2, 0), (2, 1)]),
+                ("mutated".to_string(), vec![(0, 0), (1, 1)]),
+                ("deleted".to_string(), vec![(1, 3)]),
+            ]
+        );
+        assert_eq!(parse_object_refs(EFFECTS), vec![(2, 0), (2, 1), (0, 0), (1, 1), (1, 3)]);
+        assert!(parse_object_changes("task 1, lines 3-5:\n//# run challenge::pool::swap\nmutated object: none").is_empty());
+    }
+
+    #[test]
+    fn parses_gas_used() {
+        // Computation and storage, less the rebate
+        assert_eq!(parse_gas_used(EFFECTS), Some(1000000 + 2964000 - 978120));
+        assert_eq!(parse_gas_used(EVENTS), Some(1988000));
+        assert_eq!(parse_gas_used("created: object(2,0)"), None);
+    }
+
+    #[test]
+    fn parses_events() {
+        let events = parse_events(EVENTS);
+        assert_eq!(events.len(), 2);
+
+        assert_eq!(events[0].package_id, "challenge");
+        assert_eq!(events[0].transaction_module, "pool");
+        assert_eq!(events[0].sender, "solver");
+        assert_eq!(events[0].type_, "challenge::pool::Swapped");
+        assert_eq!(events[0].contents, vec![1, 0, 0, 0, 0, 0, 0, 0]);
+
+        // Nested type parameters do not end the event early
+        assert_eq!(events[1].type_, "sui::coin::CoinMetadata");
+        assert!(events[1].contents.is_empty());
+
+        assert!(parse_events(EFFECTS).is_empty());
+    }
+
+    #[test]
+    fn parses_views() {
+        assert_eq!(parse_object_type(VIEW).as_deref(), Some("sui::coin::Coin<sui::sui::SUI>"));
+        assert_eq!(parse_object_version(VIEW), Some(3));
+        assert_eq!(parse_object_owner(VIEW).as_deref(), Some("Account Address ( solver )"));
+        assert_eq!(parse_owner_account(VIEW).as_deref(), Some("solver"));
+
+        // Only address owners are accounts
+        assert_eq!(parse_owner_account("Owner: Shared( 3 )"), None);
+        assert_eq!(parse_owner_account("Owner: Immutable"), None);
+        assert_eq!(parse_owner_account("Owner: Object ID: ( fake(2,0) )"), None);
+        assert_eq!(parse_object_type("No object at id fake(9,9)"), None);
+    }
+
+    #[test]
+    fn finds_coin_types() {
+        assert_eq!(coin_inner_type("sui::coin::Coin<sui::sui::SUI>"), Some("sui::sui::SUI"));
+        assert_eq!(coin_inner_type("0x2::coin::Coin<challenge::token::TOKEN>"), Some("challenge::token::TOKEN"));
+        assert_eq!(coin_inner_type("challenge::coin::Coin<sui::sui::SUI>"), None);
+        assert_eq!(coin_inner_type("sui::balance::Balance<sui::sui::SUI>"), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use move_binary_format::file_format::CompiledModule;
+use move_bytecode_source_map::utils::source_map_from_file;
+use move_symbol_pool::Symbol;
+use move_transactional_test_runner::framework::MaybeNamedCompiledModule;
+
+use crate::protocol::ModuleUpload;
+
+// Deserialize a module uploaded by a player, without a source map
+pub fn deserialize_module(
+    bytes: &[u8],
+    named_address: &str
+) -> Result<MaybeNamedCompiledModule, Box<dyn Error>> {
+    let module = CompiledModule::deserialize_with_defaults(bytes)
+        .map_err(|e| format!("Module deserialization failed: {}", e))?;
+
+    Ok(MaybeNamedCompiledModule {
+        named_address: Some(Symbol::from(named_address)),
+        module,
+        source_map: None,
+    })
+}
+
+// Load a module and, when present, its source map from a `sui move build` output
+pub fn load_module(
+    module_path: &Path,
+    source_map_path: Option<&Path>,
+    named_address: &str
+) -> Result<MaybeNamedCompiledModule, Box<dyn Error>> {
+    let bytes = fs::read(module_path)
+        .map_err(|e| format!("Failed to read {}: {}", module_path.display(), e))?;
+    let mut module = deserialize_module(&bytes, named_address)?;
+
+    if let Some(path) = source_map_path.filter(|p| p.exists()) {
+        let source_map = source_map_from_file(path)
+            .map_err(|e| format!("Failed to read source map {}: {}", path.display(), e))?;
+        module.source_map = Some(source_map);
+    }
+
+    Ok(module)
+}
+
+// Load every module of a built package (`<build_dir>/<package>/bytecode_modules/*.mv`),
+// sorted so that each module comes after the modules it depends on
+pub fn load_package(
+    build_dir: &Path,
+    package_name: &str,
+    named_address: &str
+) -> Result<Vec<MaybeNamedCompiledModule>, Box<dyn Error>> {
+    let package_dir = build_dir.join(package_name);
+    let bytecode_dir = package_dir.join("bytecode_modules");
+    let debug_info_dir = package_dir.join("debug_info");
+
+    let mut modules = Vec::new();
+    let entries = fs::read_dir(&bytecode_dir)
+        .map_err(|e| format!("Failed to read {}: {}", bytecode_dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("mv") {
+            continue;
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        let source_map_path = debug_info_dir.join(format!("{}.json", stem));
+        modules.push(load_module(&path, Some(&source_map_path), named_address)?);
+    }
+
+    if modules.is_empty() {
+        return Err(format!("No modules found in {}", bytecode_dir.display()).into());
+    }
+
+    sort_by_dependencies(modules)
+}
+
+// Source files copied into a `sui move build` output (`<build_dir>/<package>/sources`),
+// dependencies included, as `(path relative to the package, text)`
+pub fn load_sources(
+    build_dir: &Path,
+    package_name: &str
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let package_dir = build_dir.join(package_name);
+    let mut pending = vec![package_dir.join("sources")];
+    let mut sources = Vec::new();
+
+    while let Some(dir) = pending.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some("move") {
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                let name = path.strip_prefix(&package_dir).unwrap_or(&path).display().to_string();
+                sources.push((name, text));
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
+// Raw bytes of every module of a built package, in dependency order, ready to upload
+pub fn load_package_uploads(
+    build_dir: &Path,
+    package_name: &str
+) -> Result<Vec<ModuleUpload>, Box<dyn Error>> {
+    let bytecode_dir = build_dir.join(package_name).join("bytecode_modules");
+    let modules = load_package(build_dir, package_name, package_name)?;
+
+    let mut uploads = Vec::new();
+    for module in modules {
+        let name = module.module.name().to_string();
+        let path = bytecode_dir.join(format!("{}.mv", name));
+        let bytes = fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        uploads.push(ModuleUpload { name, bytes });
+    }
+
+    Ok(uploads)
+}
+
+// Order modules of a single package so that dependencies are published first
+pub fn sort_by_dependencies(
+    modules: Vec<MaybeNamedCompiledModule>
+) -> Result<Vec<MaybeNamedCompiledModule>, Box<dyn Error>> {
+    let names: BTreeSet<String> = modules.iter().map(|m| m.module.name().to_string()).collect();
+    let mut pending = modules;
+    let mut sorted: Vec<MaybeNamedCompiledModule> = Vec::new();
+    let mut placed: BTreeSet<String> = BTreeSet::new();
+
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|m| {
+            let self_addr = *m.module.self_id().address();
+            m.module.immediate_dependencies().iter().all(|dep| {
+                let dep_name = dep.name().to_string();
+                *dep.address() != self_addr || !names.contains(&dep_name) || placed.contains(&dep_name)
+            })
+        });
+
+        if ready.is_empty() {
+            let cycle: Vec<String> = rest.iter().map(|m| m.module.name().to_string()).collect();
+            return Err(format!("Cyclic module dependencies between: {}", cycle.join(", ")).into());
+        }
+
+        for module in ready {
+            placed.insert(module.module.name().to_string());
+            sorted.push(module);
+        }
+        pending = rest;
+    }
+
+    Ok(sorted)
+}
+
+// Build output of a Move package: `<dir>/build/<name>` when `dir` has a `Move.toml`,
+// otherwise `dir` is taken to be a build output already
+pub fn build_output_dir(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let move_toml = dir.join("Move.toml");
+    if !move_toml.is_file() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let text = fs::read_to_string(&move_toml)
+        .map_err(|e| format!("Failed to read {}: {}", move_toml.display(), e))?;
+    let parsed: toml::Value = toml::from_str(&text)
+        .map_err(|e| format!("Invalid {}: {}", move_toml.display(), e))?;
+    let name = parsed
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .ok_or_else(|| format!("{} has no package name", move_toml.display()))?;
+
+    let output = dir.join("build").join(name);
+    if !output.is_dir() {
+        return Err(format!("{} is not built, run `sui move build` in {}", name, dir.display()).into());
+    }
+    Ok(output)
+}
@@ -0,0 +1,102 @@
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+// Pre-warmed environments: `size` environments are initialized and prepared (see
+// `server::Challenge::prepare`) before any player connects, each on a thread of its own
+// as `SuiTF` cannot move between threads. A connection is handed to a waiting one, and
+// another one is started in its place. Environments older than `max_age` are rebuilt,
+// so the simulator clock of a fresh session is never far behind.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub size: usize,
+    pub max_age: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            size: 4,
+            max_age: Duration::from_secs(600),
+        }
+    }
+}
+
+// Environments waiting for work of type `T`, e.g. a player's connection. Shared by the
+// threads handing work out.
+pub struct WarmPool<T> {
+    sender: mpsc::Sender<oneshot::Sender<T>>,
+    waiting: Mutex<mpsc::Receiver<oneshot::Sender<T>>>,
+}
+
+// Held by a warm environment to wait for work
+#[derive(Clone)]
+pub struct Registrar<T> {
+    sender: mpsc::Sender<oneshot::Sender<T>>,
+}
+
+pub enum Wait<T> {
+    Ready(T),
+    // Nothing came within the maximum age, the environment should be rebuilt
+    Expired,
+    // The pool is gone
+    Closed,
+}
+
+impl<T> WarmPool<T> {
+    pub fn new() -> Self {
+        let (sender, waiting) = mpsc::channel();
+        WarmPool { sender, waiting: Mutex::new(waiting) }
+    }
+
+    pub fn registrar(&self) -> Registrar<T> {
+        Registrar { sender: self.sender.clone() }
+    }
+
+    // Hand `work` to a waiting environment, giving it back when none is waiting.
+    // Environments that expired in the meantime are skipped.
+    pub fn claim(&self, mut work: T) -> Result<(), T> {
+        let waiting = match self.waiting.lock() {
+            Ok(waiting) => waiting,
+            Err(_) => return Err(work),
+        };
+        while let Ok(environment) = waiting.try_recv() {
+            match environment.send(work) {
+                Ok(()) => return Ok(()),
+                Err(returned) => work = returned,
+            }
+        }
+        Err(work)
+    }
+}
+
+impl<T> Default for WarmPool<T> {
+    fn default() -> Self {
+        WarmPool::new()
+    }
+}
+
+impl<T> Registrar<T> {
+    // Wait for work for at most `max_age`. Awaited from the environment's own
+    // runtime, so its local tasks keep running meanwhile.
+    pub async fn wait(&self, max_age: Duration) -> Wait<T> {
+        let (sender, mut receiver) = oneshot::channel();
+        if self.sender.send(sender).is_err() {
+            return Wait::Closed;
+        }
+
+        match tokio::time::timeout(max_age, &mut receiver).await {
+            Ok(Ok(work)) => Wait::Ready(work),
+            Ok(Err(_)) => Wait::Closed,
+            Err(_) => {
+                // Work sent right as the wait ended is still taken
+                receiver.close();
+                match receiver.try_recv() {
+                    Ok(work) => Wait::Ready(work),
+                    Err(_) => Wait::Expired,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use crate::jsonl;
+
+// Hashcash-style proof of work asked before any simulator work starts:
+//
+//   server  [POW] prefix=<prefix> difficulty=<bits>
+//           [POW] Solution:
+//   client  <solution>
+//
+// The solution is valid when SHA-256(prefix || solution) starts with `difficulty` zero bits
+pub const PREFIX_LEN: usize = 16;
+pub const MAX_DIFFICULTY: u32 = 64;
+
+const MAX_SOLUTION_LEN: usize = 128;
+
+pub fn generate_prefix() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(PREFIX_LEN)
+        .map(char::from)
+        .collect()
+}
+
+pub fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+pub fn verify(
+    prefix: &str,
+    solution: &str,
+    difficulty: u32
+) -> bool {
+    let hash = Sha256::new()
+        .chain_update(prefix.as_bytes())
+        .chain_update(solution.as_bytes())
+        .finalize();
+    leading_zero_bits(&hash) >= difficulty
+}
+
+// Brute force a solution, taking about 2^difficulty hashes
+pub fn solve(
+    prefix: &str,
+    difficulty: u32
+) -> String {
+    let mut counter: u64 = 0;
+    loop {
+        let solution = counter.to_string();
+        if verify(prefix, &solution, difficulty) {
+            return solution;
+        }
+        counter += 1;
+    }
+}
+
+pub fn challenge_line(
+    prefix: &str,
+    difficulty: u32
+) -> String {
+    format!("[POW] prefix={} difficulty={}\n", prefix, difficulty)
+}
+
+// Parse the `[POW] prefix=... difficulty=...` line sent by the server
+pub fn parse_challenge_line(line: &str) -> Option<(String, u32)> {
+    let rest = line.trim().strip_prefix("[POW]")?;
+    let mut prefix = None;
+    let mut difficulty = None;
+    for part in rest.split_whitespace() {
+        if let Some(p) = part.strip_prefix("prefix=") {
+            prefix = Some(p.to_string());
+        } else if let Some(d) = part.strip_prefix("difficulty=") {
+            difficulty = d.parse().ok();
+        }
+    }
+    Some((prefix?, difficulty?))
+}
+
+// Server side: send a fresh challenge and check the client's answer
+pub fn challenge<S: BufRead + Write>(
+    stream: &mut S,
+    difficulty: u32
+) -> Result<(), Box<dyn Error>> {
+    if difficulty > MAX_DIFFICULTY {
+        return Err(format!("PoW difficulty {} is above the maximum of {}", difficulty, MAX_DIFFICULTY).into());
+    }
+
+    let prefix = generate_prefix();
+    stream.write_all(challenge_line(&prefix, difficulty).as_bytes())?;
+    stream.write_all(b"[POW] Solution: ")?;
+    stream.flush()?;
+
+    let solution = jsonl::read_line(stream, MAX_SOLUTION_LEN)?
+        .ok_or("Client disconnected before solving the proof of work")?;
+    if !verify(&prefix, &solution, difficulty) {
+        return Err("Invalid proof of work".into());
+    }
+
+    stream.write_all(b"[POW] Accepted\n")?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ScriptedStream;
+
+    // Client that answers the challenge it was sent with `answer(prefix, difficulty)`
+    fn client(answer: fn(&str, u32) -> String) -> ScriptedStream {
+        ScriptedStream::answering(move |sent| {
+            let (prefix, difficulty) = sent.lines().find_map(parse_challenge_line).expect("no challenge sent");
+            format!("{}\n", answer(&prefix, difficulty))
+        })
+    }
+
+    // A solution that does not meet `difficulty`
+    fn wrong_solution(
+        prefix: &str,
+        difficulty: u32
+    ) -> String {
+        (0u64..).map(|n| n.to_string()).find(|s| !verify(prefix, s, difficulty)).unwrap()
+    }
+
+    #[test]
+    fn counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0xff]), 0);
+        assert_eq!(leading_zero_bits(&[0x80, 0]), 0);
+        assert_eq!(leading_zero_bits(&[0x01]), 7);
+        assert_eq!(leading_zero_bits(&[0x00, 0x40]), 9);
+        assert_eq!(leading_zero_bits(&[0x00, 0x00, 0x0f]), 20);
+        assert_eq!(leading_zero_bits(&[0u8; 32]), 256);
+        assert_eq!(leading_zero_bits(&[]), 0);
+    }
+
+    #[test]
+    fn verifies_solutions() {
+        let prefix = "abcdefghijklmnop";
+        let solution = solve(prefix, 12);
+        assert!(verify(prefix, &solution, 12));
+        assert!(verify(prefix, &solution, 0));
+
+        assert!(!verify(prefix, &wrong_solution(prefix, 12), 12));
+        assert!(!verify(prefix, &solution, 256));
+    }
+
+    #[test]
+    fn parses_challenge_lines() {
+        let line = challenge_line("abc", 20);
+        assert_eq!(parse_challenge_line(&line), Some(("abc".to_string(), 20)));
+        assert_eq!(parse_challenge_line("[POW] prefix=abc"), None);
+        assert_eq!(parse_challenge_line("[POW] prefix=abc difficulty=x"), None);
+        assert_eq!(parse_challenge_line("prefix=abc difficulty=20"), None);
+    }
+
+    #[test]
+    fn prefixes_are_fresh() {
+        let prefix = generate_prefix();
+        assert_eq!(prefix.len(), PREFIX_LEN);
+        assert!(prefix.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(prefix, generate_prefix());
+    }
+
+    #[test]
+    fn challenge_accepts_solution() {
+        let mut client = client(solve);
+        challenge(&mut client, 8).unwrap();
+        assert!(client.sent_text().ends_with("[POW] Accepted\n"));
+    }
+
+    #[test]
+    fn challenge_rejects_wrong_solution() {
+        let mut client = client(wrong_solution);
+        let err = challenge(&mut client, 8).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid proof of work");
+        assert!(!client.sent_text().contains("Accepted"));
+    }
+
+    #[test]
+    fn challenge_rejects_over_difficulty() {
+        let mut client = client(solve);
+        let err = challenge(&mut client, MAX_DIFFICULTY + 1).unwrap_err();
+        assert!(err.to_string().contains("above the maximum"));
+        // Nothing is sent for a request that could never be solved
+        assert!(client.sent.is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+use std::error::Error;
+use std::io::{Read, Write};
+
+use sha2::{Digest, Sha256};
+
+// Framed module upload:
+//
+//   magic     "SCTF"
+//   version   u8
+//   flags     u8      (bit 0: SHA-256 checksum of the payload follows it)
+//   length    u32 BE  (payload length, checksum excluded)
+//   payload   version 2 only: named address length u16 BE (0 if unset), named address,
+//                             dependency count u16 BE, then per dependency: length u16 BE, name
+//             count u16 BE, then per module:
+//             name length u16 BE, name (UTF-8), bytes length u32 BE, bytes
+//   checksum  [u8; 32] (optional)
+pub const UPLOAD_MAGIC: &[u8; 4] = b"SCTF";
+pub const UPLOAD_VERSION: u8 = 2;
+pub const DEFAULT_MAX_UPLOAD_SIZE: usize = 1024 * 1024;
+
+const MODULES_ONLY_VERSION: u8 = 1;
+const FLAG_CHECKSUM: u8 = 0x01;
+const MAX_NAME_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleUpload {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+// A whole solution package: its modules in dependency order, the named address
+// they are published under and the already published packages they depend on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageUpload {
+    pub named_address: Option<String>,
+    pub dependencies: Vec<String>,
+    pub modules: Vec<ModuleUpload>,
+}
+
+fn push_name(
+    payload: &mut Vec<u8>,
+    name: &str
+) -> Result<(), Box<dyn Error>> {
+    if name.len() > MAX_NAME_LEN {
+        return Err(format!("Name too long: '{}'", name).into());
+    }
+    payload.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    payload.extend_from_slice(name.as_bytes());
+    Ok(())
+}
+
+fn encode_frame(
+    version: u8,
+    payload: Vec<u8>,
+    checksum: bool
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let payload_len = u32::try_from(payload.len()).map_err(|_| "Upload too large")?;
+
+    let mut frame = Vec::with_capacity(10 + payload.len() + 32);
+    frame.extend_from_slice(UPLOAD_MAGIC);
+    frame.push(version);
+    frame.push(if checksum { FLAG_CHECKSUM } else { 0 });
+    frame.extend_from_slice(&payload_len.to_be_bytes());
+    frame.extend_from_slice(&payload);
+    if checksum {
+        frame.extend_from_slice(&Sha256::digest(&payload));
+    }
+
+    Ok(frame)
+}
+
+fn encode_module_list(
+    payload: &mut Vec<u8>,
+    modules: &[ModuleUpload]
+) -> Result<(), Box<dyn Error>> {
+    let count = u16::try_from(modules.len()).map_err(|_| "Too many modules in upload")?;
+    payload.extend_from_slice(&count.to_be_bytes());
+    for module in modules {
+        if module.name.is_empty() {
+            return Err("Module name cannot be empty".into());
+        }
+        let bytes_len = u32::try_from(module.bytes.len()).map_err(|_| "Module too large")?;
+        push_name(payload, &module.name)?;
+        payload.extend_from_slice(&bytes_len.to_be_bytes());
+        payload.extend_from_slice(&module.bytes);
+    }
+    Ok(())
+}
+
+pub fn encode_modules(
+    modules: &[ModuleUpload],
+    checksum: bool
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut payload = Vec::new();
+    encode_module_list(&mut payload, modules)?;
+    encode_frame(MODULES_ONLY_VERSION, payload, checksum)
+}
+
+pub fn encode_package(
+    package: &PackageUpload,
+    checksum: bool
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut payload = Vec::new();
+    push_name(&mut payload, package.named_address.as_deref().unwrap_or(""))?;
+    let dep_count = u16::try_from(package.dependencies.len()).map_err(|_| "Too many dependencies")?;
+    payload.extend_from_slice(&dep_count.to_be_bytes());
+    for dep in package.dependencies.iter() {
+        push_name(&mut payload, dep)?;
+    }
+    encode_module_list(&mut payload, &package.modules)?;
+    encode_frame(UPLOAD_VERSION, payload, checksum)
+}
+
+pub fn write_modules<W: Write>(
+    writer: &mut W,
+    modules: &[ModuleUpload],
+    checksum: bool
+) -> Result<(), Box<dyn Error>> {
+    let frame = encode_modules(modules, checksum)?;
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_package<W: Write>(
+    writer: &mut W,
+    package: &PackageUpload,
+    checksum: bool
+) -> Result<(), Box<dyn Error>> {
+    let frame = encode_package(package, checksum)?;
+    writer.write_all(&frame)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_modules<R: Read>(
+    reader: &mut R,
+    max_size: usize
+) -> Result<Vec<ModuleUpload>, Box<dyn Error>> {
+    Ok(read_package(reader, max_size)?.modules)
+}
+
+// Read an upload of either version; modules-only uploads carry no named address or dependencies
+pub fn read_package<R: Read>(
+    reader: &mut R,
+    max_size: usize
+) -> Result<PackageUpload, Box<dyn Error>> {
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header).map_err(|e| format!("Failed to read upload header: {}", e))?;
+
+    if &header[..4] != UPLOAD_MAGIC {
+        return Err("Invalid upload header, expected a framed module upload".into());
+    }
+    let version = header[4];
+    if version != MODULES_ONLY_VERSION && version != UPLOAD_VERSION {
+        return Err(format!("Unsupported upload version {}", version).into());
+    }
+    let flags = header[5];
+    let payload_len = u32::from_be_bytes(header[6..10].try_into().unwrap()) as usize;
+    if payload_len > max_size {
+        return Err(format!("Upload too large: {} bytes (max {})", payload_len, max_size).into());
+    }
+
+    let mut payload = vec![0u8; payload_len];
+    reader.read_exact(&mut payload).map_err(|e| format!("Failed to read upload payload: {}", e))?;
+
+    if flags & FLAG_CHECKSUM != 0 {
+        let mut checksum = [0u8; 32];
+        reader.read_exact(&mut checksum).map_err(|e| format!("Failed to read upload checksum: {}", e))?;
+        if Sha256::digest(&payload).as_slice() != checksum {
+            return Err("Upload checksum mismatch".into());
+        }
+    }
+
+    decode_payload(version, &payload)
+}
+
+struct PayloadReader<'a> {
+    payload: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PayloadReader<'a> {
+    fn take(
+        &mut self,
+        len: usize
+    ) -> Result<&'a [u8], Box<dyn Error>> {
+        let slice = self.payload.get(self.pos..self.pos + len).ok_or("Truncated upload payload")?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn name(&mut self) -> Result<String, Box<dyn Error>> {
+        let len = self.u16()? as usize;
+        if len > MAX_NAME_LEN {
+            return Err(format!("Invalid name length {}", len).into());
+        }
+        Ok(String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Name is not UTF-8")?)
+    }
+}
+
+fn decode_payload(
+    version: u8,
+    payload: &[u8]
+) -> Result<PackageUpload, Box<dyn Error>> {
+    let mut reader = PayloadReader { payload, pos: 0 };
+    let mut package = PackageUpload::default();
+
+    if version == UPLOAD_VERSION {
+        let named_address = reader.name()?;
+        if !named_address.is_empty() {
+            package.named_address = Some(named_address);
+        }
+        for _ in 0..reader.u16()? {
+            package.dependencies.push(reader.name()?);
+        }
+    }
+
+    let count = reader.u16()?;
+    if count == 0 {
+        return Err("Upload contains no modules".into());
+    }
+
+    for _ in 0..count {
+        let name = reader.name()?;
+        if name.is_empty() {
+            return Err("Module name cannot be empty".into());
+        }
+        let bytes_len = reader.u32()? as usize;
+        let bytes = reader.take(bytes_len)?.to_vec();
+        package.modules.push(ModuleUpload { name, bytes });
+    }
+
+    if reader.pos != payload.len() {
+        return Err("Trailing data after upload payload".into());
+    }
+
+    Ok(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(
+        name: &str,
+        bytes: &[u8]
+    ) -> ModuleUpload {
+        ModuleUpload { name: name.to_string(), bytes: bytes.to_vec() }
+    }
+
+    fn package() -> PackageUpload {
+        PackageUpload {
+            named_address: Some("solution".to_string()),
+            dependencies: vec!["challenge".to_string()],
+            modules: vec![module("a", &[0xa1, 0x1c, 0xeb, 0x0b]), module("b", &[1, 2, 3])],
+        }
+    }
+
+    fn read(frame: &[u8]) -> Result<PackageUpload, Box<dyn Error>> {
+        read_package(&mut &frame[..], DEFAULT_MAX_UPLOAD_SIZE)
+    }
+
+    #[test]
+    fn modules_round_trip() {
+        let modules = vec![module("solution", &[0xa1, 0x1c, 0xeb, 0x0b, 6]), module("helper", &[])];
+        for checksum in [false, true] {
+            let frame = encode_modules(&modules, checksum).unwrap();
+            assert_eq!(frame[4], MODULES_ONLY_VERSION);
+            assert_eq!(read_modules(&mut &frame[..], DEFAULT_MAX_UPLOAD_SIZE).unwrap(), modules);
+
+            // Modules-only uploads read as packages without a named address or dependencies
+            let package = read(&frame).unwrap();
+            assert_eq!(package.named_address, None);
+            assert!(package.dependencies.is_empty());
+        }
+    }
+
+    #[test]
+    fn package_round_trip() {
+        for checksum in [false, true] {
+            let frame = encode_package(&package(), checksum).unwrap();
+            assert_eq!(frame[4], UPLOAD_VERSION);
+            assert_eq!(read(&frame).unwrap(), package());
+        }
+
+        let mut writer = Vec::new();
+        write_package(&mut writer, &package(), true).unwrap();
+        assert_eq!(read(&writer).unwrap(), package());
+    }
+
+    #[test]
+    fn package_without_named_address() {
+        let mut upload = package();
+        upload.named_address = None;
+        let frame = encode_package(&upload, false).unwrap();
+        assert_eq!(read(&frame).unwrap(), upload);
+    }
+
+    #[test]
+    fn rejects_bad_magic() {
+        let mut frame = encode_package(&package(), false).unwrap();
+        frame[0] = b'X';
+        assert!(read(&frame).unwrap_err().to_string().contains("Invalid upload header"));
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut frame = encode_package(&package(), false).unwrap();
+        frame[4] = 3;
+        assert!(read(&frame).unwrap_err().to_string().contains("Unsupported upload version 3"));
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let mut frame = encode_package(&package(), true).unwrap();
+        let last = frame.len() - 1;
+        frame[last] ^= 0xff;
+        assert!(read(&frame).unwrap_err().to_string().contains("checksum mismatch"));
+
+        // A tampered payload fails the same way
+        let mut frame = encode_package(&package(), true).unwrap();
+        frame[12] ^= 0xff;
+        assert!(read(&frame).unwrap_err().to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn rejects_payload_over_max_size() {
+        let frame = encode_package(&package(), false).unwrap();
+        let payload_len = frame.len() - 10;
+        let err = read_package(&mut &frame[..], payload_len - 1).unwrap_err();
+        assert!(err.to_string().contains("Upload too large"));
+        assert!(read_package(&mut &frame[..], payload_len).is_ok());
+
+        // The declared length is checked before anything is allocated
+        let mut header = frame[..10].to_vec();
+        header[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read(&header).unwrap_err().to_string().contains("Upload too large"));
+    }
+
+    #[test]
+    fn rejects_long_names() {
+        let long = "a".repeat(MAX_NAME_LEN + 1);
+        assert!(encode_modules(&[module(&long, &[1])], false).is_err());
+
+        // A decoder never trusts the encoder to have checked
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&1u16.to_be_bytes());
+        payload.extend_from_slice(&(long.len() as u16).to_be_bytes());
+        payload.extend_from_slice(long.as_bytes());
+        payload.extend_from_slice(&1u32.to_be_bytes());
+        payload.push(1);
+        let frame = encode_frame(MODULES_ONLY_VERSION, payload, false).unwrap();
+        assert!(read(&frame).unwrap_err().to_string().contains("Invalid name length"));
+    }
+
+    #[test]
+    fn rejects_module_length_past_payload() {
+        let mut payload = Vec::new();
+        encode_module_list(&mut payload, &[module("a", &[1, 2, 3])]).unwrap();
+        let len_at = payload.len() - 3 - 4;
+        payload[len_at..len_at + 4].copy_from_slice(&1000u32.to_be_bytes());
+        let frame = encode_frame(MODULES_ONLY_VERSION, payload, false).unwrap();
+        assert!(read(&frame).unwrap_err().to_string().contains("Truncated upload payload"));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let frame = encode_package(&package(), true).unwrap();
+        assert!(read(&frame[..6]).unwrap_err().to_string().contains("upload header"));
+        assert!(read(&frame[..frame.len() - 40]).unwrap_err().to_string().contains("upload payload"));
+        assert!(read(&frame[..frame.len() - 1]).unwrap_err().to_string().contains("upload checksum"));
+    }
+
+    #[test]
+    fn rejects_empty_and_trailing_payloads() {
+        let frame = encode_frame(MODULES_ONLY_VERSION, 0u16.to_be_bytes().to_vec(), false).unwrap();
+        assert!(read(&frame).unwrap_err().to_string().contains("no modules"));
+
+        let mut payload = Vec::new();
+        encode_module_list(&mut payload, &[module("a", &[1])]).unwrap();
+        payload.push(0);
+        let frame = encode_frame(MODULES_ONLY_VERSION, payload, false).unwrap();
+        assert!(read(&frame).unwrap_err().to_string().contains("Trailing data"));
+    }
+}
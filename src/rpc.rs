@@ -0,0 +1,1097 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use sui_transactional_test_runner::{args::SuiExtraValueArgs, test_adapter::FakeID};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    digests::{ObjectDigest, TransactionDigest},
+    transaction::{
+        Argument, CallArg, Command, ObjectArg, ProgrammableTransaction, TransactionData,
+        TransactionDataAPI, TransactionKind,
+    },
+};
+
+use move_binary_format::file_format::{
+    Ability, AbilitySet, CompiledModule, DatatypeHandleIndex, SignatureToken, StructFieldInformation, Visibility,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    parsing::{address::ParsedAddress, values::ParsedValue},
+    u256::U256,
+};
+use move_compiler::shared::{NumberFormat, NumericalAddress};
+use move_symbol_pool::Symbol;
+use move_transactional_test_runner::framework::MaybeNamedCompiledModule;
+
+use crate::{output, SuiTF};
+
+// Enumerated objects are exposed to RPC clients under synthetic object IDs:
+// this prefix followed by the task and index as big-endian u64s
+const FAKE_ID_PREFIX: [u8; 16] = *b"sui-ctf-fake-id\0";
+const MAX_REQUEST_SIZE: usize = 16 * 1024 * 1024;
+const SUI_COIN_TYPE: &str = "0x2::sui::SUI";
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Subset of the Sui JSON-RPC API served from a `SuiTF` instance
+pub struct RpcServer {
+    listener: TcpListener,
+}
+
+impl RpcServer {
+    pub async fn bind(addr: &str) -> Result<RpcServer, Box<dyn Error>> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        if !local_addr.ip().is_loopback() {
+            return Err(format!("JSON-RPC endpoint must listen on localhost, got {}", local_addr).into());
+        }
+
+        info!(address = %local_addr, "JSON-RPC endpoint listening");
+        Ok(RpcServer { listener })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Requests are answered one at a time, as they all act on the same `SuiTF`
+    pub async fn serve(
+        &self,
+        suitf: &mut SuiTF
+    ) -> Result<(), Box<dyn Error>> {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    if let Err(e) = handle_connection(suitf, stream).await {
+                        warn!(error = %e, "JSON-RPC request failed");
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "JSON-RPC connection error");
+                }
+            }
+        }
+    }
+}
+
+async fn handle_connection(
+    suitf: &mut SuiTF,
+    mut stream: TcpStream
+) -> Result<(), Box<dyn Error>> {
+    let (method, body) = tokio::time::timeout(IO_TIMEOUT, read_http_request(&mut stream))
+        .await
+        .map_err(|_| "Timed out reading the HTTP request")??;
+
+    // CORS preflight from browser-based SDKs
+    if method == "OPTIONS" {
+        return write_http_response(&mut stream, "204 No Content", "").await;
+    }
+    if method != "POST" {
+        return write_http_response(&mut stream, "405 Method Not Allowed", "").await;
+    }
+
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => {
+            let mut responses = Vec::new();
+            for request in requests {
+                responses.push(handle_request(suitf, request).await);
+            }
+            Value::Array(responses)
+        }
+        Ok(request) => handle_request(suitf, request).await,
+        Err(e) => error_response(Value::Null, -32700, format!("Parse error: {}", e)),
+    };
+
+    write_http_response(&mut stream, "200 OK", &response.to_string()).await
+}
+
+async fn read_http_request(stream: &mut TcpStream) -> Result<(String, Vec<u8>), Box<dyn Error>> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    // Read up to the end of the headers
+    let header_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if data.len() > MAX_REQUEST_SIZE {
+            return Err("HTTP headers too large".into());
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err("Connection closed before end of HTTP headers".into());
+        }
+        data.extend_from_slice(&buf[..n]);
+    };
+
+    let headers = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let method = headers.split_whitespace().next().unwrap_or("").to_string();
+    let content_length = headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            value.trim().parse::<usize>().ok()
+        } else {
+            None
+        }
+    }).unwrap_or(0);
+
+    if content_length > MAX_REQUEST_SIZE {
+        return Err(format!("HTTP body too large: {} bytes", content_length).into());
+    }
+
+    let mut body = data[header_end..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err("Connection closed before end of HTTP body".into());
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    body.truncate(content_length);
+
+    Ok((method, body))
+}
+
+async fn write_http_response(
+    stream: &mut TcpStream,
+    status: &str,
+    body: &str
+) -> Result<(), Box<dyn Error>> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: POST, OPTIONS\r\nAccess-Control-Allow-Headers: *\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    tokio::time::timeout(IO_TIMEOUT, async {
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await
+    })
+    .await
+    .map_err(|_| "Timed out writing the HTTP response")??;
+    Ok(())
+}
+
+fn error_response(
+    id: Value,
+    code: i64,
+    message: String
+) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+async fn handle_request(
+    suitf: &mut SuiTF,
+    request: Value
+) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = match request.get("method").and_then(|m| m.as_str()) {
+        Some(method) => method.to_string(),
+        None => return error_response(id, -32600, "Invalid request: missing method".to_string()),
+    };
+    let params = match request.get("params") {
+        Some(Value::Array(params)) => params.clone(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(_) => return error_response(id, -32602, "Only positional params are supported".to_string()),
+    };
+
+    debug!(method = %method, "JSON-RPC call");
+    let result = match method.as_str() {
+        "sui_getObject" => get_object(suitf, &params).await,
+        "sui_multiGetObjects" => multi_get_objects(suitf, &params).await,
+        "suix_getOwnedObjects" => get_owned_objects(suitf, &params).await,
+        "suix_getCoins" => get_coins(suitf, &params).await,
+        "suix_getBalance" => get_balance(suitf, &params).await,
+        "sui_executeTransactionBlock" => execute_transaction_block(suitf, &params, false).await,
+        "sui_dryRunTransactionBlock" => execute_transaction_block(suitf, &params, true).await,
+        "sui_getNormalizedMoveModule" => get_normalized_move_module(suitf, &params),
+        "suix_queryEvents" => query_events(suitf, &params),
+        "sui_getChainIdentifier" => Ok(json!("sui-ctf")),
+        "suix_getReferenceGasPrice" => Ok(json!("1000")),
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    };
+
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, message),
+    }
+}
+
+type RpcResult = Result<Value, (i64, String)>;
+
+fn invalid_params(msg: impl std::fmt::Display) -> (i64, String) {
+    (-32602, format!("Invalid params: {}", msg))
+}
+
+fn param_str<'a>(
+    params: &'a [Value],
+    idx: usize,
+    name: &str
+) -> Result<&'a str, (i64, String)> {
+    params
+        .get(idx)
+        .and_then(|p| p.as_str())
+        .ok_or_else(|| invalid_params(format!("missing '{}'", name)))
+}
+
+fn param_object_id(
+    params: &[Value],
+    idx: usize,
+    name: &str
+) -> Result<ObjectID, (i64, String)> {
+    let id = param_str(params, idx, name)?;
+    ObjectID::from_hex_literal(id).map_err(|e| invalid_params(format!("bad {} '{}': {}", name, id, e)))
+}
+
+fn param_address(
+    params: &[Value],
+    idx: usize,
+    name: &str
+) -> Result<AccountAddress, (i64, String)> {
+    let addr = param_str(params, idx, name)?;
+    SuiAddress::from_str(addr)
+        .map(AccountAddress::from)
+        .map_err(|e| invalid_params(format!("bad {} '{}': {}", name, addr, e)))
+}
+
+pub fn fake_to_object_id(task: u64, idx: u64) -> ObjectID {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&FAKE_ID_PREFIX);
+    bytes[16..24].copy_from_slice(&task.to_be_bytes());
+    bytes[24..].copy_from_slice(&idx.to_be_bytes());
+    ObjectID::new(bytes)
+}
+
+// The simulator's digests are not reported by the adapter. Objects and transactions get
+// stable synthetic digests instead: the same object version or transaction of a session
+// always has the same digest, and different ones differ. Input digests are not checked.
+pub fn object_digest(
+    object_id: ObjectID,
+    version: u64
+) -> ObjectDigest {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sui-ctf-object");
+    hasher.update(object_id.into_bytes());
+    hasher.update(version.to_le_bytes());
+    ObjectDigest::new(hasher.finalize().into())
+}
+
+// Digest of the transaction with sequence number `seq`, see `SuiTF::get_history`
+pub fn transaction_digest(seq: u64) -> TransactionDigest {
+    let mut hasher = Sha256::new();
+    hasher.update(b"sui-ctf-transaction");
+    hasher.update(seq.to_le_bytes());
+    TransactionDigest::new(hasher.finalize().into())
+}
+
+pub fn object_id_to_fake(id: ObjectID) -> FakeID {
+    let bytes = id.into_bytes();
+    if bytes[..16] == FAKE_ID_PREFIX {
+        let task = u64::from_be_bytes(bytes[16..24].try_into().unwrap());
+        let idx = u64::from_be_bytes(bytes[24..].try_into().unwrap());
+        FakeID::Enumerated(task, idx)
+    } else {
+        FakeID::Known(id)
+    }
+}
+
+// Replace `fake(x,y)` / `object(x,y)` placeholders and account names with addresses
+fn resolve_name(
+    suitf: &SuiTF,
+    name: &str
+) -> String {
+    let name = name.trim();
+    for prefix in ["fake(", "object("] {
+        if let Some(inner) = name.strip_prefix(prefix).and_then(|n| n.strip_suffix(')')) {
+            if let Some((task, idx)) = inner.split_once(',') {
+                if let (Ok(task), Ok(idx)) = (task.trim().parse(), idx.trim().parse()) {
+                    return fake_to_object_id(task, idx).to_string();
+                }
+            }
+        }
+    }
+    if let Some(addr) = suitf.get_account_address(name).or_else(|| suitf.get_package_address(name)) {
+        return addr.to_hex_literal();
+    }
+    name.to_string()
+}
+
+fn resolve_value(
+    suitf: &SuiTF,
+    value: Value
+) -> Value {
+    match value {
+        Value::String(s) => Value::String(resolve_name(suitf, s.trim_end_matches(','))),
+        Value::Object(map) => Value::Object(
+            map.into_iter().map(|(k, v)| (k, resolve_value(suitf, v))).collect()
+        ),
+        other => other,
+    }
+}
+
+fn owner_json(
+    suitf: &SuiTF,
+    owner: &str
+) -> Value {
+    let inner = |prefix: &str| {
+        owner.strip_prefix(prefix)
+            .map(|rest| rest.trim().trim_start_matches('(').trim_end_matches(')').trim().to_string())
+    };
+
+    if let Some(addr) = inner("Account Address") {
+        json!({ "AddressOwner": resolve_name(suitf, &addr) })
+    } else if let Some(id) = inner("Object ID:") {
+        json!({ "ObjectOwner": resolve_name(suitf, &id) })
+    } else if let Some(version) = inner("Shared") {
+        json!({ "Shared": { "initial_shared_version": version.parse::<u64>().unwrap_or(0) } })
+    } else if owner == "Immutable" {
+        json!("Immutable")
+    } else {
+        json!(owner)
+    }
+}
+
+// Objects the player asked for are recorded as views, scans over every known object
+// as the framework's lookups
+async fn object_data(
+    suitf: &mut SuiTF,
+    fake: FakeID,
+    object_id: ObjectID,
+    asked: bool
+) -> Option<Value> {
+    let raw = if asked {
+        suitf.view_object_raw(fake).await
+    } else {
+        suitf.lookup_object(fake).await
+    };
+    let raw = match raw {
+        Ok(Some(raw)) => raw,
+        _ => return None,
+    };
+
+    let type_ = output::parse_object_type(&raw).unwrap_or_default();
+    let owner = output::parse_object_owner(&raw).map(|o| owner_json(suitf, &o)).unwrap_or(Value::Null);
+    let version = output::parse_object_version(&raw).unwrap_or(0);
+    let fields = SuiTF::parse_output(&raw)
+        .get("Contents")
+        .cloned()
+        .map(|fields| resolve_value(suitf, fields))
+        .unwrap_or(Value::Null);
+
+    Some(json!({
+        "objectId": object_id.to_string(),
+        "version": version.to_string(),
+        "digest": object_digest(object_id, version).to_string(),
+        "type": type_,
+        "owner": owner,
+        "content": {
+            "dataType": "moveObject",
+            "type": type_,
+            "fields": fields,
+        },
+    }))
+}
+
+fn object_not_found(object_id: ObjectID) -> Value {
+    json!({ "error": { "code": "notExists", "object_id": object_id.to_string() } })
+}
+
+async fn get_object(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let object_id = param_object_id(params, 0, "object_id")?;
+    match object_data(suitf, object_id_to_fake(object_id), object_id, true).await {
+        Some(data) => Ok(json!({ "data": data })),
+        None => Ok(object_not_found(object_id)),
+    }
+}
+
+async fn multi_get_objects(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let ids = params
+        .first()
+        .and_then(|p| p.as_array())
+        .ok_or_else(|| invalid_params("missing 'object_ids'"))?;
+
+    let mut objects = Vec::new();
+    for id in ids {
+        let object_id = id
+            .as_str()
+            .and_then(|id| ObjectID::from_hex_literal(id).ok())
+            .ok_or_else(|| invalid_params(format!("bad object id {}", id)))?;
+        match object_data(suitf, object_id_to_fake(object_id), object_id, true).await {
+            Some(data) => objects.push(json!({ "data": data })),
+            None => objects.push(object_not_found(object_id)),
+        }
+    }
+    Ok(Value::Array(objects))
+}
+
+// Objects owned by `owner`, among every object the session has seen created
+async fn owned_objects(
+    suitf: &mut SuiTF,
+    owner: AccountAddress
+) -> Vec<Value> {
+    let owner = owner.to_hex_literal();
+    let mut objects = Vec::new();
+
+    for fake in suitf.get_known_objects() {
+        let object_id = match fake {
+            FakeID::Enumerated(task, idx) => fake_to_object_id(task, idx),
+            FakeID::Known(id) => id,
+        };
+        if let Some(data) = object_data(suitf, fake, object_id, false).await {
+            if data["owner"]["AddressOwner"].as_str() == Some(owner.as_str()) {
+                objects.push(data);
+            }
+        }
+    }
+    objects
+}
+
+async fn get_owned_objects(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let owner = param_address(params, 0, "address")?;
+    let struct_type = params
+        .get(1)
+        .and_then(|q| q.pointer("/filter/StructType"))
+        .and_then(|t| t.as_str())
+        .map(|t| t.to_string());
+
+    let data: Vec<Value> = owned_objects(suitf, owner)
+        .await
+        .into_iter()
+        .filter(|obj| match &struct_type {
+            Some(t) => output::type_matches(suitf, obj["type"].as_str().unwrap_or(""), t),
+            None => true,
+        })
+        .map(|obj| json!({ "data": obj }))
+        .collect();
+
+    Ok(json!({ "data": data, "nextCursor": null, "hasNextPage": false }))
+}
+
+async fn coins(
+    suitf: &mut SuiTF,
+    owner: AccountAddress,
+    coin_type: &str
+) -> Vec<Value> {
+    owned_objects(suitf, owner)
+        .await
+        .into_iter()
+        .filter_map(|obj| {
+            let inner = output::coin_inner_type(obj["type"].as_str()?)?;
+            if !output::type_matches(suitf, inner, coin_type) {
+                return None;
+            }
+            Some(json!({
+                "coinType": coin_type,
+                "coinObjectId": obj["objectId"],
+                "version": obj["version"],
+                "digest": obj["digest"],
+                "balance": obj.pointer("/content/fields/balance/value").cloned().unwrap_or(json!("0")),
+            }))
+        })
+        .collect()
+}
+
+async fn get_coins(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let owner = param_address(params, 0, "owner")?;
+    let coin_type = params.get(1).and_then(|t| t.as_str()).unwrap_or(SUI_COIN_TYPE).to_string();
+    let data = coins(suitf, owner, &coin_type).await;
+    Ok(json!({ "data": data, "nextCursor": null, "hasNextPage": false }))
+}
+
+async fn get_balance(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let owner = param_address(params, 0, "owner")?;
+    let coin_type = params.get(1).and_then(|t| t.as_str()).unwrap_or(SUI_COIN_TYPE).to_string();
+    let data = coins(suitf, owner, &coin_type).await;
+    let total: u128 = data
+        .iter()
+        .filter_map(|c| c["balance"].as_str()?.parse::<u128>().ok())
+        .sum();
+    Ok(json!({
+        "coinType": coin_type,
+        "coinObjectCount": data.len(),
+        "totalBalance": total.to_string(),
+        "lockedBalance": {},
+    }))
+}
+
+fn get_normalized_move_module(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let package = param_address(params, 0, "package")?;
+    let module = param_str(params, 1, "module_name")?;
+    suitf
+        .module_map
+        .get(&package)
+        .and_then(|modules| modules.iter().find(|m| m.name().as_str() == module))
+        .map(normalized_module)
+        .ok_or_else(|| (-32000, format!("Module {}::{} not found", package.to_hex_literal(), module)))
+}
+
+// `SuiMoveNormalizedModule`, as the Sui SDKs expect it
+fn normalized_module(module: &CompiledModule) -> Value {
+    let mut structs = serde_json::Map::new();
+    for def in module.struct_defs() {
+        let handle = module.datatype_handle_at(def.struct_handle);
+        let fields = match &def.field_information {
+            StructFieldInformation::Native => Vec::new(),
+            StructFieldInformation::Declared(fields) => fields.iter().map(|field| json!({
+                "name": module.identifier_at(field.name).to_string(),
+                "type": normalized_type(module, &field.signature.0),
+            })).collect(),
+        };
+        structs.insert(module.identifier_at(handle.name).to_string(), json!({
+            "abilities": normalized_abilities(handle.abilities),
+            "typeParameters": normalized_datatype_params(module, def.struct_handle),
+            "fields": fields,
+        }));
+    }
+
+    let mut enums = serde_json::Map::new();
+    for def in module.enum_defs() {
+        let handle = module.datatype_handle_at(def.enum_handle);
+        let mut variants = serde_json::Map::new();
+        let mut order = Vec::new();
+        for variant in def.variants.iter() {
+            let name = module.identifier_at(variant.variant_name).to_string();
+            let fields: Vec<Value> = variant.fields.iter().map(|field| json!({
+                "name": module.identifier_at(field.name).to_string(),
+                "type": normalized_type(module, &field.signature.0),
+            })).collect();
+            variants.insert(name.clone(), json!(fields));
+            order.push(name);
+        }
+        enums.insert(module.identifier_at(handle.name).to_string(), json!({
+            "abilities": normalized_abilities(handle.abilities),
+            "typeParameters": normalized_datatype_params(module, def.enum_handle),
+            "variants": variants,
+            "variantDeclarationOrder": order,
+        }));
+    }
+
+    // Private functions are only exposed when they are entry functions
+    let mut functions = serde_json::Map::new();
+    for def in module.function_defs() {
+        if def.visibility == Visibility::Private && !def.is_entry {
+            continue;
+        }
+        let handle = module.function_handle_at(def.function);
+        let visibility = match def.visibility {
+            Visibility::Public => "Public",
+            Visibility::Friend => "Friend",
+            Visibility::Private => "Private",
+        };
+        let types = |idx| -> Vec<Value> {
+            module.signature_at(idx).0.iter().map(|token| normalized_type(module, token)).collect()
+        };
+        functions.insert(module.identifier_at(handle.name).to_string(), json!({
+            "visibility": visibility,
+            "isEntry": def.is_entry,
+            "typeParameters": handle.type_parameters.iter().map(|c| normalized_abilities(*c)).collect::<Vec<_>>(),
+            "parameters": types(handle.parameters),
+            "return": types(handle.return_),
+        }));
+    }
+
+    let friends: Vec<Value> = module.friend_decls().iter().map(|friend| json!({
+        "address": module.address_identifier_at(friend.address).to_hex_literal(),
+        "name": module.identifier_at(friend.name).to_string(),
+    })).collect();
+
+    json!({
+        "fileFormatVersion": module.version,
+        "address": module.address().to_hex_literal(),
+        "name": module.name().to_string(),
+        "friends": friends,
+        "structs": structs,
+        "enums": enums,
+        "exposedFunctions": functions,
+    })
+}
+
+fn normalized_abilities(abilities: AbilitySet) -> Value {
+    let names: Vec<&str> = abilities.into_iter().map(|ability| match ability {
+        Ability::Copy => "Copy",
+        Ability::Drop => "Drop",
+        Ability::Store => "Store",
+        Ability::Key => "Key",
+    }).collect();
+    json!({ "abilities": names })
+}
+
+fn normalized_datatype_params(
+    module: &CompiledModule,
+    idx: DatatypeHandleIndex
+) -> Vec<Value> {
+    module.datatype_handle_at(idx).type_parameters.iter().map(|param| json!({
+        "constraints": normalized_abilities(param.constraints),
+        "isPhantom": param.is_phantom,
+    })).collect()
+}
+
+// `SuiMoveNormalizedType`
+fn normalized_type(
+    module: &CompiledModule,
+    token: &SignatureToken
+) -> Value {
+    let datatype = |idx: DatatypeHandleIndex, type_args: &[SignatureToken]| {
+        let handle = module.datatype_handle_at(idx);
+        let module_handle = module.module_handle_at(handle.module);
+        json!({ "Struct": {
+            "address": module.address_identifier_at(module_handle.address).to_hex_literal(),
+            "module": module.identifier_at(module_handle.name).to_string(),
+            "name": module.identifier_at(handle.name).to_string(),
+            "typeArguments": type_args.iter().map(|arg| normalized_type(module, arg)).collect::<Vec<_>>(),
+        }})
+    };
+    match token {
+        SignatureToken::Bool => json!("Bool"),
+        SignatureToken::U8 => json!("U8"),
+        SignatureToken::U16 => json!("U16"),
+        SignatureToken::U32 => json!("U32"),
+        SignatureToken::U64 => json!("U64"),
+        SignatureToken::U128 => json!("U128"),
+        SignatureToken::U256 => json!("U256"),
+        SignatureToken::Address => json!("Address"),
+        SignatureToken::Signer => json!("Signer"),
+        SignatureToken::Vector(inner) => json!({ "Vector": normalized_type(module, inner) }),
+        SignatureToken::Datatype(idx) => datatype(*idx, &[]),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, type_args) = &**inst;
+            datatype(*idx, type_args)
+        }
+        SignatureToken::Reference(inner) => json!({ "Reference": normalized_type(module, inner) }),
+        SignatureToken::MutableReference(inner) => json!({ "MutableReference": normalized_type(module, inner) }),
+        SignatureToken::TypeParameter(idx) => json!({ "TypeParameter": idx }),
+    }
+}
+
+fn event_json(
+    suitf: &SuiTF,
+    seq: usize,
+    event: &output::EventRecord
+) -> Value {
+    json!({
+        "id": { "txDigest": transaction_digest(event.transaction).to_string(), "eventSeq": seq.to_string() },
+        "packageId": resolve_name(suitf, &event.package_id),
+        "transactionModule": event.transaction_module,
+        "sender": resolve_name(suitf, &event.sender),
+        "type": event.type_,
+        "bcs": BASE64.encode(&event.contents),
+    })
+}
+
+fn query_events(
+    suitf: &mut SuiTF,
+    params: &[Value]
+) -> RpcResult {
+    let filter = params.first().cloned().unwrap_or(json!("All"));
+    let limit = params.get(2).and_then(|l| l.as_u64()).map(|l| l as usize).unwrap_or(usize::MAX);
+    let descending = params.get(3).and_then(|d| d.as_bool()).unwrap_or(false);
+
+    let mut data: Vec<Value> = suitf
+        .get_events()
+        .iter()
+        .enumerate()
+        .map(|(seq, event)| event_json(suitf, seq, event))
+        .filter(|event| {
+            if let Some(t) = filter.get("MoveEventType").and_then(|t| t.as_str()) {
+                output::type_matches(suitf, event["type"].as_str().unwrap_or(""), t)
+            } else if let Some(sender) = filter.get("Sender").and_then(|s| s.as_str()) {
+                event["sender"].as_str() == Some(sender)
+            } else if let Some(module) = filter.get("MoveModule") {
+                event["packageId"] == module["package"] && event["transactionModule"] == module["module"]
+            } else {
+                true
+            }
+        })
+        .collect();
+
+    if descending {
+        data.reverse();
+    }
+    data.truncate(limit);
+
+    Ok(json!({ "data": data, "nextCursor": null, "hasNextPage": false }))
+}
+
+async fn execute_transaction_block(
+    suitf: &mut SuiTF,
+    params: &[Value],
+    dry_run: bool
+) -> RpcResult {
+    let tx_bytes = BASE64
+        .decode(param_str(params, 0, "tx_bytes")?)
+        .map_err(|e| invalid_params(format!("tx_bytes is not base64: {}", e)))?;
+    let tx_data: TransactionData = bcs::from_bytes(&tx_bytes)
+        .map_err(|e| invalid_params(format!("tx_bytes is not a TransactionData: {}", e)))?;
+
+    // Signatures are not checked, the simulator signs for its own accounts
+    let sender = AccountAddress::from(tx_data.sender());
+    let sender_name = suitf
+        .get_account_name(&sender)
+        .ok_or_else(|| (-32000, format!("Sender {} is not an account of this session", sender.to_hex_literal())))?;
+
+    let ptb = match tx_data.kind() {
+        TransactionKind::ProgrammableTransaction(ptb) => ptb.clone(),
+        _ => return Err((-32000, "Only programmable transactions are supported".to_string())),
+    };
+
+    let events_before = suitf.get_events().len();
+    // Every transaction is recorded, including dry runs, see `transaction_digest`
+    let digest = transaction_digest(suitf.get_history().len() as u64);
+    let mut object_changes = Vec::new();
+    let result = match ptb.commands.first() {
+        Some(Command::Publish(modules, deps)) => {
+            let published = match check_publish(&ptb, sender) {
+                Err(e) => Err(e),
+                // The adapter cannot dry-run a publish, it runs in a copy of the session
+                // instead, see `SuiTF::fork`
+                Ok(()) if dry_run => match suitf.fork().await {
+                    Ok(mut copy) => publish_package(&mut copy, sender_name, modules, deps)
+                        .await
+                        .map(|(addr, output)| (published_change(&copy, addr), output)),
+                    Err(e) => Err(format!("Dry run failed: {}", e)),
+                },
+                Ok(()) => publish_package(suitf, sender_name, modules, deps)
+                    .await
+                    .map(|(addr, output)| (published_change(suitf, addr), output)),
+            };
+            published.map(|(change, output)| {
+                object_changes.push(change);
+                output
+            })
+        }
+        _ => {
+            let (inputs, commands) = translate_ptb(suitf, &ptb).map_err(|e| (-32000, e))?;
+            suitf.programmable_transaction(
+                sender_name,
+                inputs,
+                commands,
+                Some(tx_data.gas_budget()),
+                dry_run,
+            ).await.map(|out| out.unwrap_or_default()).map_err(|e| e.to_string())
+        }
+    };
+
+    let (status, output) = match result {
+        Ok(output) => (json!({ "status": "success" }), output),
+        Err(e) => (json!({ "status": "failure", "error": e }), String::new()),
+    };
+
+    let mut effects = serde_json::Map::new();
+    effects.insert("status".to_string(), status);
+    for (kind, refs) in output::parse_object_changes(&output) {
+        let ids: Vec<Value> = refs
+            .into_iter()
+            .map(|(task, idx)| json!({ "reference": { "objectId": fake_to_object_id(task, idx).to_string() } }))
+            .collect();
+        effects.insert(kind, Value::Array(ids));
+    }
+
+    let events: Vec<Value> = suitf.get_events()[events_before..]
+        .iter()
+        .enumerate()
+        .map(|(seq, event)| event_json(suitf, events_before + seq, event))
+        .collect();
+
+    Ok(json!({
+        "digest": digest.to_string(),
+        "effects": Value::Object(effects),
+        "events": events,
+        "objectChanges": object_changes,
+        "rawOutput": output,
+    }))
+}
+
+// The adapter publishes outside programmable transactions and hands the upgrade cap
+// to the sender, so besides the publish a transaction may only transfer that cap to
+// the sender, as `sui client publish` and the SDKs do
+fn check_publish(
+    ptb: &ProgrammableTransaction,
+    sender: AccountAddress
+) -> Result<(), String> {
+    for command in ptb.commands.iter().skip(1) {
+        let transfers_cap = match command {
+            Command::TransferObjects(objects, Argument::Input(i)) => {
+                objects[..] == [Argument::Result(0)]
+                    && matches!(
+                        ptb.inputs.get(*i as usize),
+                        Some(CallArg::Pure(bytes)) if bcs::from_bytes::<AccountAddress>(bytes).ok() == Some(sender)
+                    )
+            }
+            _ => false,
+        };
+        if !transfers_cap {
+            return Err(format!(
+                "Unsupported command after a publish: {:?}. A publish can only be followed by the transfer of its upgrade cap to the sender",
+                command
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn published_change(
+    suitf: &SuiTF,
+    addr: AccountAddress
+) -> Value {
+    json!({
+        "type": "published",
+        "packageId": addr.to_hex_literal(),
+        "version": "1",
+        "digest": object_digest(ObjectID::from(addr), 1).to_string(),
+        "modules": suitf.get_package_modules(addr).unwrap_or_default(),
+    })
+}
+
+async fn publish_package(
+    suitf: &mut SuiTF,
+    sender_name: String,
+    modules: &[Vec<u8>],
+    deps: &[ObjectID]
+) -> Result<(AccountAddress, String), String> {
+    let package_name = format!("rpc_package_{}", suitf.package_map.len());
+
+    let mut compiled_modules = Vec::new();
+    for bytes in modules {
+        let module = CompiledModule::deserialize_with_defaults(bytes)
+            .map_err(|e| format!("Module deserialization failed: {}", e))?;
+        compiled_modules.push(MaybeNamedCompiledModule {
+            named_address: Some(Symbol::from(package_name.as_str())),
+            module,
+            source_map: None,
+        });
+    }
+
+    // Framework packages are always available, other dependencies are referenced by name
+    let dependencies: Vec<String> = deps
+        .iter()
+        .filter_map(|id| suitf.get_package_name(&AccountAddress::from(*id)))
+        .collect();
+
+    let addr = suitf
+        .publish_compiled_module(compiled_modules, dependencies, Some(sender_name))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((addr, format!("published: {}", addr.to_hex_literal())))
+}
+
+fn argument_text(arg: &Argument) -> String {
+    match arg {
+        Argument::GasCoin => "Gas".to_string(),
+        Argument::Input(i) => format!("Input({})", i),
+        Argument::Result(i) => format!("Result({})", i),
+        Argument::NestedResult(i, j) => format!("NestedResult({},{})", i, j),
+    }
+}
+
+fn arguments_text(args: &[Argument]) -> String {
+    args.iter().map(argument_text).collect::<Vec<_>>().join(", ")
+}
+
+// Translate a programmable transaction into `//>` lines and typed inputs
+fn translate_ptb(
+    suitf: &SuiTF,
+    ptb: &ProgrammableTransaction
+) -> Result<(Vec<ParsedValue<SuiExtraValueArgs>>, Vec<String>), String> {
+    let mut hints: Vec<Option<String>> = vec![None; ptb.inputs.len()];
+    let mut hint = |arg: &Argument, type_: &str| {
+        if let Argument::Input(i) = arg {
+            if let Some(slot) = hints.get_mut(*i as usize) {
+                slot.get_or_insert_with(|| type_.to_string());
+            }
+        }
+    };
+
+    let mut commands = Vec::new();
+    for command in ptb.commands.iter() {
+        let text = match command {
+            Command::MoveCall(call) => {
+                let package = AccountAddress::from(call.package);
+                if let Some(function) = suitf
+                    .get_module_abi(package, &call.module.to_string())
+                    .and_then(|abi| abi.function(&call.function.to_string()).cloned())
+                {
+                    for (arg, param) in call.arguments.iter().zip(function.parameters.iter()) {
+                        hint(arg, param.trim_start_matches("&mut ").trim_start_matches('&'));
+                    }
+                }
+
+                let type_args = if call.type_arguments.is_empty() {
+                    String::new()
+                } else {
+                    let tys: Vec<String> = call.type_arguments
+                        .iter()
+                        .map(|t| t.to_canonical_string(/* with_prefix */ true))
+                        .collect();
+                    format!("<{}>", tys.join(", "))
+                };
+                format!(
+                    "{}::{}::{}{}({})",
+                    package.to_hex_literal(),
+                    call.module,
+                    call.function,
+                    type_args,
+                    arguments_text(&call.arguments)
+                )
+            }
+            Command::TransferObjects(objects, recipient) => {
+                hint(recipient, "address");
+                format!("TransferObjects([{}], {})", arguments_text(objects), argument_text(recipient))
+            }
+            Command::SplitCoins(coin, amounts) => {
+                for amount in amounts.iter() {
+                    hint(amount, "u64");
+                }
+                format!("SplitCoins({}, [{}])", argument_text(coin), arguments_text(amounts))
+            }
+            Command::MergeCoins(coin, coins) => {
+                format!("MergeCoins({}, [{}])", argument_text(coin), arguments_text(coins))
+            }
+            Command::MakeMoveVec(type_, elems) => match type_ {
+                Some(t) => {
+                    let t = t.to_canonical_string(/* with_prefix */ true);
+                    for elem in elems.iter() {
+                        hint(elem, &t);
+                    }
+                    format!("MakeMoveVec<{}>([{}])", t, arguments_text(elems))
+                }
+                None => format!("MakeMoveVec([{}])", arguments_text(elems)),
+            },
+            Command::Publish(..) => {
+                return Err("Publish must be the first command of its transaction".to_string());
+            }
+            Command::Upgrade(..) => {
+                return Err("Package upgrades are not supported".to_string());
+            }
+        };
+        commands.push(text);
+    }
+
+    let mut inputs = Vec::new();
+    for (input, hint) in ptb.inputs.iter().zip(hints.iter()) {
+        let value = match input {
+            CallArg::Pure(bytes) => pure_to_value(bytes, hint.as_deref())?,
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _))) => {
+                ParsedValue::Custom(SuiExtraValueArgs::Object(object_id_to_fake(*id), None))
+            }
+            CallArg::Object(ObjectArg::SharedObject { id, mutable, .. }) => {
+                if *mutable {
+                    ParsedValue::Custom(SuiExtraValueArgs::Object(object_id_to_fake(*id), None))
+                } else {
+                    ParsedValue::Custom(SuiExtraValueArgs::ImmShared(object_id_to_fake(*id), None))
+                }
+            }
+            CallArg::Object(ObjectArg::Receiving((id, _, _))) => {
+                ParsedValue::Custom(SuiExtraValueArgs::Receiving(object_id_to_fake(*id), None))
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err("Unsupported transaction input".to_string()),
+        };
+        inputs.push(value);
+    }
+
+    Ok((inputs, commands))
+}
+
+pub fn read_uleb128(bytes: &[u8], pos: &mut usize) -> Result<usize, String> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos).ok_or("Truncated length prefix")?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value as usize);
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err("Invalid length prefix".to_string());
+        }
+    }
+}
+
+pub fn take<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    let slice = bytes.get(*pos..*pos + len).ok_or("Truncated pure input")?;
+    *pos += len;
+    Ok(slice)
+}
+
+// Decode a BCS-encoded pure input as a Move value of the given type
+fn decode_pure(
+    bytes: &[u8],
+    pos: &mut usize,
+    type_: &str
+) -> Result<ParsedValue<SuiExtraValueArgs>, String> {
+    let type_ = type_.trim();
+    let value = match type_ {
+        "bool" => ParsedValue::Bool(take(bytes, pos, 1)?[0] != 0),
+        "u8" => ParsedValue::U8(take(bytes, pos, 1)?[0]),
+        "u16" => ParsedValue::U16(u16::from_le_bytes(take(bytes, pos, 2)?.try_into().unwrap())),
+        "u32" => ParsedValue::U32(u32::from_le_bytes(take(bytes, pos, 4)?.try_into().unwrap())),
+        "u64" => ParsedValue::U64(u64::from_le_bytes(take(bytes, pos, 8)?.try_into().unwrap())),
+        "u128" => ParsedValue::U128(u128::from_le_bytes(take(bytes, pos, 16)?.try_into().unwrap())),
+        "u256" => ParsedValue::U256(U256::from_le_bytes(&take(bytes, pos, 32)?.try_into().unwrap())),
+        "address" | "0x2::object::ID" => {
+            let addr: [u8; 32] = take(bytes, pos, 32)?.try_into().unwrap();
+            ParsedValue::Address(ParsedAddress::Numerical(NumericalAddress::new(addr, NumberFormat::Hex)))
+        }
+        t if t.ends_with("::string::String") => decode_pure(bytes, pos, "vector<u8>")?,
+        t if t.starts_with("vector<") && t.ends_with('>') => {
+            let inner = &t["vector<".len()..t.len() - 1];
+            let len = read_uleb128(bytes, pos)?;
+            let mut elems = Vec::with_capacity(len.min(bytes.len()));
+            for _ in 0..len {
+                elems.push(decode_pure(bytes, pos, inner)?);
+            }
+            ParsedValue::Vector(elems)
+        }
+        t => return Err(format!("Unsupported pure input type {}", t)),
+    };
+    Ok(value)
+}
+
+// Pure inputs are untyped bytes, their type comes from the parameter they are passed to
+fn pure_to_value(
+    bytes: &[u8],
+    hint: Option<&str>
+) -> Result<ParsedValue<SuiExtraValueArgs>, String> {
+    let type_ = hint.ok_or_else(|| {
+        "Pure input of unknown type: pass it to a function of a published package, or to a command taking a u64 or an address".to_string()
+    })?;
+
+    let mut pos = 0;
+    let value = decode_pure(bytes, &mut pos, type_)?;
+    if pos != bytes.len() {
+        return Err(format!("Pure input does not match type {}", type_));
+    }
+    Ok(value)
+}
@@ -0,0 +1,728 @@
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::{debug, error, info, instrument, warn};
+
+use move_compiler::shared::NumericalAddress;
+use move_core_types::account_address::AccountAddress;
+
+use crate::{abort::MoveAbort, flag::{self, FlagProvider}, jsonl, limits::{SessionLimits, SessionMeter}, package, pool::{PoolConfig, Registrar, Wait, WarmPool}, pow, protocol, transcript, worker, FrameworkSource, SuiTF};
+
+pub struct ServerConfig {
+    pub bind_addr: String,
+    pub read_timeout: Duration,
+    pub write_timeout: Duration,
+    pub named_addresses: Vec<(String, NumericalAddress)>,
+    pub accounts: Vec<String>,
+    pub max_upload_size: usize,
+    // Leading zero bits of the proof of work asked before initialization, `None` to disable
+    pub pow_difficulty: Option<u32>,
+    pub limits: SessionLimits,
+    pub flag: FlagProvider,
+    // Directory receiving a transcript per session (see `transcript`), `None` to disable
+    pub transcript_dir: Option<PathBuf>,
+    // How much of an error players are told, see `PlayerErrors`
+    pub player_errors: PlayerErrors,
+    // Sessions served at the same time, further players are turned away after the handshake
+    pub max_sessions: usize,
+    // Connections in the proof of work and team token prompts at the same time, and how
+    // long each prompt waits for an answer, see `ChallengeServer::handshake`
+    pub max_handshakes: usize,
+    pub handshake_timeout: Duration,
+    // Environments prepared ahead of connections, `None` to prepare one per connection
+    pub pool: Option<PoolConfig>,
+    // Framework Move sources are compiled against, see `FrameworkSource`
+    pub framework: FrameworkSource,
+}
+
+// What players see of an error, the full error is always logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerErrors {
+    // The whole error text, adapter errors included
+    Full,
+    // Decoded Move aborts (see `abort::MoveAbort`), other errors only name the failing step
+    Aborts,
+    // Only the failing step
+    Hidden,
+}
+
+impl PlayerErrors {
+    // Error text for the player, `None` when it stays on the server
+    pub fn show(
+        &self,
+        err: &(dyn Error + 'static)
+    ) -> Option<String> {
+        match self {
+            PlayerErrors::Full => Some(err.to_string()),
+            PlayerErrors::Aborts => err.downcast_ref::<MoveAbort>().map(|abort| abort.to_string()),
+            PlayerErrors::Hidden => None,
+        }
+    }
+
+    // `<step>: <error>`, or the step alone when the error is not shown
+    pub fn describe(
+        &self,
+        step: &str,
+        err: &(dyn Error + 'static)
+    ) -> String {
+        match self.show(err) {
+            Some(text) => format!("{}: {}", step, text),
+            None => step.to_string(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_addr: "0.0.0.0:31337".to_string(),
+            read_timeout: Duration::from_secs(300),
+            write_timeout: Duration::from_secs(30),
+            named_addresses: vec![
+                ("challenge".to_string(), NumericalAddress::parse_str("0x0").unwrap()),
+                ("solution".to_string(), NumericalAddress::parse_str("0x0").unwrap()),
+            ],
+            accounts: vec!["challenger".to_string(), "solver".to_string()],
+            max_upload_size: protocol::DEFAULT_MAX_UPLOAD_SIZE,
+            pow_difficulty: None,
+            limits: SessionLimits::default(),
+            flag: FlagProvider::from_env(),
+            transcript_dir: None,
+            player_errors: PlayerErrors::Full,
+            max_sessions: 16,
+            max_handshakes: 64,
+            handshake_timeout: Duration::from_secs(30),
+            pool: None,
+            framework: FrameworkSource::Bundled,
+        }
+    }
+}
+
+// Hooks a challenge author implements; the server drives them once per connection
+#[allow(async_fn_in_trait)]
+pub trait Challenge {
+    // Work that does not involve the player, such as publishing the challenge. With a
+    // pool (see `ServerConfig::pool`) it runs before the player connects.
+    async fn prepare(&self, _suitf: &mut SuiTF) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    // Talk to the player before the solution, e.g. send the challenge address, after
+    // `prepare`. Work that does not need the player belongs in `prepare`.
+    async fn setup(&self, session: &mut Session) -> Result<(), Box<dyn Error>>;
+
+    // Receive and run the player's solution
+    async fn solve(&self, session: &mut Session) -> Result<(), Box<dyn Error>>;
+
+    // Whether the win condition has been reached
+    async fn check(&self, session: &mut Session) -> Result<bool, Box<dyn Error>>;
+}
+
+// A solution package published on behalf of the player
+#[derive(Debug, Clone)]
+pub struct PublishedPackage {
+    pub named_address: String,
+    pub address: AccountAddress,
+    pub modules: Vec<String>,
+}
+
+// Publish a built package from the server's filesystem, e.g. the challenge itself in
+// `Challenge::prepare`. Its sources are kept to decode aborts, see `abort`.
+pub async fn publish_package(
+    suitf: &mut SuiTF,
+    build_dir: &Path,
+    package_name: &str,
+    named_address: &str,
+    dependencies: Vec<String>,
+    sender: &str
+) -> Result<AccountAddress, Box<dyn Error>> {
+    let modules = package::load_package(build_dir, package_name, named_address)?;
+    for (name, text) in package::load_sources(build_dir, package_name)? {
+        suitf.add_source(&name, text);
+    }
+    suitf.publish_compiled_module(modules, dependencies, Some(sender.to_string())).await
+}
+
+// `<dir>/<unix millis>-<team token or peer address>.jsonl`
+fn transcript_path(
+    dir: &Path,
+    stream: &TcpStream,
+    team_token: Option<&str>
+) -> PathBuf {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let who = match team_token {
+        Some(token) => token.to_string(),
+        None => stream
+            .peer_addr()
+            .map(|addr| addr.to_string().replace([':', '.', '[', ']'], "_"))
+            .unwrap_or_else(|_| "unknown".to_string()),
+    };
+    dir.join(format!("{}-{}.jsonl", millis, who))
+}
+
+// Validate an uploaded package and publish it as `sender`.
+// The package may pick its own named address and any subset of `allowed_dependencies`,
+// it depends on all of them when it names none.
+pub async fn publish_upload(
+    suitf: &mut SuiTF,
+    upload: protocol::PackageUpload,
+    default_named_address: &str,
+    allowed_dependencies: &[String],
+    sender: &str
+) -> Result<PublishedPackage, Box<dyn Error>> {
+    let named_address = upload.named_address.unwrap_or_else(|| default_named_address.to_string());
+    // Only a new name or an unpublished placeholder (`solution = 0x0`) can be claimed,
+    // publishing under a framework, account or package name would rebind it in the adapter
+    let taken = match suitf.get_named_address(&named_address) {
+        Some(addr) => addr != AccountAddress::ZERO || suitf.get_package_address(&named_address).is_some(),
+        None => false,
+    };
+    if taken {
+        return Err(format!("Named address '{}' is already in use", named_address).into());
+    }
+
+    let dependencies = if upload.dependencies.is_empty() {
+        allowed_dependencies.to_vec()
+    } else {
+        for dep in upload.dependencies.iter() {
+            if !allowed_dependencies.contains(dep) {
+                return Err(format!(
+                    "Dependency '{}' is not allowed, available: {}",
+                    dep,
+                    allowed_dependencies.join(", ")
+                ).into());
+            }
+        }
+        upload.dependencies
+    };
+
+    let mut modules = Vec::new();
+    for module in upload.modules {
+        info!(module = %module.name, bytes = module.bytes.len(), "received module");
+        let compiled = package::deserialize_module(&module.bytes, &named_address)?;
+        if compiled.module.name().as_str() != module.name {
+            return Err(format!(
+                "Module uploaded as '{}' is named '{}'",
+                module.name,
+                compiled.module.name()
+            ).into());
+        }
+        modules.push(compiled);
+    }
+    let modules = package::sort_by_dependencies(modules)?;
+    let module_names: Vec<String> = modules.iter().map(|m| m.module.name().to_string()).collect();
+
+    let package_addr = suitf.publish_compiled_module(
+        modules,
+        dependencies,
+        Some(sender.to_string()),
+    ).await?;
+    info!(package = %named_address, address = %package_addr, "published uploaded package");
+
+    Ok(PublishedPackage {
+        named_address,
+        address: package_addr,
+        modules: module_names,
+    })
+}
+
+// A connected player and their isolated Sui environment
+pub struct Session {
+    pub suitf: SuiTF,
+    stream: jsonl::BufStream<TcpStream>,
+    max_upload_size: usize,
+    read_timeout: Duration,
+    meter: SessionMeter,
+    flag: FlagProvider,
+    team_token: Option<String>,
+    player_errors: PlayerErrors,
+}
+
+impl Session {
+    // Player input must be read through the returned stream, which may hold buffered bytes
+    pub fn stream(&mut self) -> &mut jsonl::BufStream<TcpStream> {
+        &mut self.stream
+    }
+
+    // What the player is told of errors, from `ServerConfig::player_errors`
+    pub fn player_errors(&self) -> PlayerErrors {
+        self.player_errors
+    }
+
+    pub fn team_token(&self) -> Option<&str> {
+        self.team_token.as_deref()
+    }
+
+    // The flag for this player, per team when the provider needs it
+    pub fn flag(&self) -> Result<String, Box<dyn Error>> {
+        self.flag.flag(self.team_token.as_deref())
+    }
+
+    pub fn meter(&self) -> &SessionMeter {
+        &self.meter
+    }
+
+    pub fn meter_mut(&mut self) -> &mut SessionMeter {
+        &mut self.meter
+    }
+
+    // Default JSON-lines settings with the server's upload limit, see `serve_commands`
+    pub fn command_config(&self) -> jsonl::CommandConfig {
+        jsonl::CommandConfig {
+            max_upload_size: self.max_upload_size,
+            ..jsonl::CommandConfig::default()
+        }
+    }
+
+    // Fail once the session has run out of time, and never wait on the player past that point
+    fn apply_deadline(&mut self) -> Result<(), Box<dyn Error>> {
+        self.meter.check_time()?;
+        let timeout = match self.meter.remaining_time() {
+            Some(remaining) => remaining.min(self.read_timeout),
+            None => self.read_timeout,
+        };
+        self.stream.get_ref().set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        Ok(())
+    }
+
+    pub fn send(
+        &mut self,
+        msg: &str
+    ) -> Result<(), Box<dyn Error>> {
+        self.stream.write_all(msg.as_bytes())?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    pub fn read_input(
+        &mut self,
+        max_len: usize
+    ) -> Result<String, Box<dyn Error>> {
+        self.apply_deadline()?;
+        let mut buf = vec![0u8; max_len];
+        let n = self.stream.read(&mut buf)?;
+        if n == 0 {
+            return Err("Client disconnected".into());
+        }
+        Ok(String::from_utf8_lossy(&buf[..n]).trim().to_string())
+    }
+
+    // Read a framed module upload, see `protocol`
+    pub fn read_modules(&mut self) -> Result<Vec<protocol::ModuleUpload>, Box<dyn Error>> {
+        self.apply_deadline()?;
+        protocol::read_modules(&mut self.stream, self.max_upload_size)
+    }
+
+    // Publish a built package from the server's filesystem, see `publish_package`
+    pub async fn publish_package(
+        &mut self,
+        build_dir: &Path,
+        package_name: &str,
+        named_address: &str,
+        dependencies: Vec<String>,
+        sender: &str
+    ) -> Result<AccountAddress, Box<dyn Error>> {
+        publish_package(&mut self.suitf, build_dir, package_name, named_address, dependencies, sender).await
+    }
+
+    // Read the player's modules from the socket and publish them as `solver`
+    pub async fn publish_solution(
+        &mut self,
+        named_address: &str,
+        dependencies: Vec<String>
+    ) -> Result<AccountAddress, Box<dyn Error>> {
+        let published = self.publish_solution_package(named_address, &dependencies).await?;
+        Ok(published.address)
+    }
+
+    // Read a whole solution package and publish all of its modules in a single transaction,
+    // see `publish_upload`
+    pub async fn publish_solution_package(
+        &mut self,
+        default_named_address: &str,
+        allowed_dependencies: &[String]
+    ) -> Result<PublishedPackage, Box<dyn Error>> {
+        self.apply_deadline()?;
+        let upload = protocol::read_package(&mut self.stream, self.max_upload_size)?;
+
+        let module_count = upload.modules.len();
+        self.meter.check_publish(module_count)?;
+        let gas_before = self.suitf.get_gas_used();
+        let result = publish_upload(&mut self.suitf, upload, default_named_address, allowed_dependencies, "solver").await;
+        self.meter.charge_transaction(&self.suitf, gas_before, module_count, result.is_ok());
+        let published = result?;
+
+        let module_list: Vec<String> = published.modules
+            .iter()
+            .map(|name| format!("{}::{}", published.address, name))
+            .collect();
+        self.send(&format!(
+            "[SERVER] Solution published at {}\n[SERVER] Modules: {}\n",
+            published.address,
+            module_list.join(", ")
+        ))?;
+
+        Ok(published)
+    }
+
+    // Answer JSON-lines commands (see `jsonl`) until the client exits or disconnects.
+    // `get_flag` runs the challenge's `check` and replies with the flag once it passes.
+    pub async fn serve_commands<C: Challenge>(
+        &mut self,
+        challenge: &C,
+        config: &jsonl::CommandConfig
+    ) -> Result<(), Box<dyn Error>> {
+        loop {
+            if let Err(e) = self.apply_deadline() {
+                jsonl::write_response(&mut self.stream, &serde_json::Value::Null, Err(e.to_string()))?;
+                return Ok(());
+            }
+            let line = match jsonl::read_line(&mut self.stream, config.max_line_len())? {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            let request = match jsonl::parse_request(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    jsonl::write_response(&mut self.stream, &serde_json::Value::Null, Err(e))?;
+                    continue;
+                }
+            };
+            let id = request.get("id").cloned().unwrap_or(serde_json::Value::Null);
+
+            let result = match jsonl::command_name(&request) {
+                Some("exit") => {
+                    jsonl::write_response(&mut self.stream, &id, Ok(serde_json::Value::Null))?;
+                    return Ok(());
+                }
+                Some("get_flag") => match challenge.check(self).await {
+                    Ok(true) => match self.flag() {
+                        Ok(flag) => Ok(serde_json::json!({ "flag": flag })),
+                        Err(e) => {
+                            error!(error = %e, "flag unavailable");
+                            Err("Flag not found, please contact admin".to_string())
+                        }
+                    },
+                    Ok(false) => Err("Solution did not reach the win condition".to_string()),
+                    Err(e) => {
+                        warn!(error = %e, "solution check failed");
+                        Err(self.player_errors.describe("Solution check failed", &*e))
+                    }
+                },
+                _ => {
+                    let player_errors = self.player_errors;
+                    jsonl::dispatch(&mut self.suitf, config, &mut self.meter, &request)
+                        .await
+                        .map_err(|e| player_errors.show(&*e).unwrap_or_else(|| "Request failed".to_string()))
+                }
+            };
+            jsonl::write_response(&mut self.stream, &id, result)?;
+        }
+    }
+}
+
+// A connection that passed `ChallengeServer::handshake`, waiting for an environment
+pub struct Player {
+    pub stream: jsonl::BufStream<TcpStream>,
+    pub team_token: Option<String>,
+}
+
+pub struct ChallengeServer<C: Challenge> {
+    challenge: C,
+    config: ServerConfig,
+}
+
+fn report_error<W: Write>(
+    stream: &mut W,
+    player_errors: PlayerErrors,
+    msg: &str,
+    err: Box<dyn Error>
+) -> Box<dyn Error> {
+    let full = format!("[SERVER ERROR] {}: {}", msg, err);
+    error!(error = %err, "{}", msg);
+    let shown = format!("[SERVER ERROR] {}", player_errors.describe(msg, &*err));
+    let _ = stream.write_all(shown.as_bytes());   // ignore write failures
+    let _ = stream.write_all(b"\n[SERVER] Connection will be closed due to error.\n");
+    let _ = stream.flush();
+    full.into()
+}
+
+impl<C: Challenge + Sync> ChallengeServer<C> {
+    pub fn new(
+        challenge: C,
+        config: ServerConfig
+    ) -> Self {
+        ChallengeServer { challenge, config }
+    }
+
+    // Serve players concurrently, each session on a thread of its own since `SuiTF`
+    // is not `Send` (see `worker`), up to `ServerConfig::max_sessions` at a time. A
+    // session slot is only taken once the handshake passed, so connections that never
+    // answer it cost nothing but a handshake slot until `handshake_timeout`.
+    // Blocks the calling thread for as long as the server runs: call it from `main`,
+    // not from inside an async runtime.
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind(&self.config.bind_addr)?;
+        info!(address = %self.config.bind_addr, "server started");
+
+        let active = AtomicUsize::new(0);
+        let handshakes = AtomicUsize::new(0);
+        let pool = WarmPool::new();
+        thread::scope(|scope| {
+            if let Some(config) = &self.config.pool {
+                for _ in 0..config.size {
+                    self.spawn_warm(scope, pool.registrar(), &active);
+                }
+            }
+
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!(error = %e, "failed to accept connection");
+                        continue;
+                    }
+                };
+                let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+
+                if handshakes.fetch_add(1, Ordering::SeqCst) >= self.config.max_handshakes {
+                    handshakes.fetch_sub(1, Ordering::SeqCst);
+                    warn!(peer = %peer, "too many pending connections, connection refused");
+                    let _ = stream.write_all(b"[SERVER] Too many players, please try again later\n");
+                    continue;
+                }
+                info!(peer = %peer, "new connection");
+
+                let (active, handshakes, pool) = (&active, &handshakes, &pool);
+                let spawned = thread::Builder::new()
+                    .name(format!("session-{}", peer))
+                    .spawn_scoped(scope, move || {
+                        let player = self.handshake(stream);
+                        handshakes.fetch_sub(1, Ordering::SeqCst);
+                        let mut player = match player {
+                            Ok(player) => player,
+                            Err(e) => {
+                                warn!(error = %e, "connection closed");
+                                return;
+                            }
+                        };
+
+                        // Session slots and warm environments only go to players who passed the handshake
+                        if active.fetch_add(1, Ordering::SeqCst) >= self.config.max_sessions {
+                            active.fetch_sub(1, Ordering::SeqCst);
+                            warn!(peer = %peer, "too many sessions, connection refused");
+                            let _ = player.stream.write_all(b"[SERVER] Too many players, please try again later\n");
+                            let _ = player.stream.flush();
+                            return;
+                        }
+
+                        // A warm environment takes the player, and another one takes its place
+                        let player = match pool.claim(player) {
+                            Ok(()) => {
+                                self.spawn_warm(scope, pool.registrar(), active);
+                                return;
+                            }
+                            Err(player) => {
+                                if self.config.pool.is_some() {
+                                    warn!("no warm environment left, preparing one for the connection");
+                                }
+                                player
+                            }
+                        };
+
+                        match worker::block_on_local(self.serve_session(player, None)) {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => warn!(error = %e, "connection closed"),
+                            Err(e) => error!(error = %e, "session failed to start"),
+                        }
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    });
+                if let Err(e) = spawned {
+                    handshakes.fetch_sub(1, Ordering::SeqCst);
+                    error!(error = %e, "failed to start a session thread");
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    // Keep a prepared environment on a thread of its own until a player is handed
+    // to it, rebuilding it when it gets older than `PoolConfig::max_age`
+    fn spawn_warm<'scope, 'env>(
+        &'env self,
+        scope: &'scope thread::Scope<'scope, 'env>,
+        registrar: Registrar<Player>,
+        active: &'env AtomicUsize
+    ) {
+        let max_age = self.config.pool.as_ref().map(|config| config.max_age).unwrap_or_default();
+        // The future holds a `SuiTF`, so it is created on the thread that runs it
+        let warm = move || async move {
+            loop {
+                let suitf = match self.prepare_environment().await {
+                    Ok(suitf) => suitf,
+                    Err((step, e)) => {
+                        // The same setup would fail again, connections fall back to a fresh environment
+                        error!(error = %e, "{} for a warm environment", step);
+                        return;
+                    }
+                };
+                debug!("warm environment ready");
+
+                match registrar.wait(max_age).await {
+                    Wait::Ready(player) => {
+                        if let Err(e) = self.serve_session(player, Some(suitf)).await {
+                            warn!(error = %e, "connection closed");
+                        }
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        return;
+                    }
+                    Wait::Expired => debug!("warm environment expired, rebuilding it"),
+                    Wait::Closed => return,
+                }
+            }
+        };
+
+        let spawned = thread::Builder::new()
+            .name("warm-environment".to_string())
+            .spawn_scoped(scope, move || {
+                if let Err(e) = worker::block_on_local(warm()) {
+                    error!(error = %e, "warm environment failed to start");
+                }
+            });
+        if let Err(e) = spawned {
+            error!(error = %e, "failed to start a warm environment thread");
+        }
+    }
+
+    // Initialized environment after `Challenge::prepare`, or the failing step and its error
+    async fn prepare_environment(&self) -> Result<SuiTF, (&'static str, Box<dyn Error>)> {
+        let mut suitf = SuiTF::initialize_with_framework(
+            self.config.named_addresses.clone(),
+            Some(self.config.accounts.clone()),
+            &self.config.framework,
+        ).await.map_err(|e| ("SuiTF initialization failed", e))?;
+        self.challenge.prepare(&mut suitf).await.map_err(|e| ("Challenge setup failed", e))?;
+        Ok(suitf)
+    }
+
+    pub async fn handle_client(
+        &self,
+        stream: TcpStream
+    ) -> Result<(), Box<dyn Error>> {
+        let player = self.handshake(stream)?;
+        self.serve_session(player, None).await
+    }
+
+    // Set up the connection and check the player before any simulator work: proof of
+    // work first, then the team token when flags are per team. Each prompt waits at
+    // most `ServerConfig::handshake_timeout`.
+    #[instrument(name = "handshake", skip_all, fields(peer = ?stream.peer_addr().ok()))]
+    pub fn handshake(
+        &self,
+        stream: TcpStream
+    ) -> Result<Player, Box<dyn Error>> {
+        stream.set_read_timeout(Some(self.config.handshake_timeout.min(self.config.read_timeout)))?;
+        stream.set_write_timeout(Some(self.config.write_timeout))?;
+        let mut stream = jsonl::BufStream::new(stream);
+
+        // Make flooding connections expensive
+        if let Some(difficulty) = self.config.pow_difficulty {
+            if let Err(e) = pow::challenge(&mut stream, difficulty) {
+                return Err(report_error(&mut stream, self.config.player_errors, "Proof of work failed", e));
+            }
+        }
+
+        let team_token = if self.config.flag.needs_team_token() {
+            match flag::read_team_token(&mut stream) {
+                Ok(token) => Some(token),
+                Err(e) => return Err(report_error(&mut stream, self.config.player_errors, "Invalid team token", e)),
+            }
+        } else {
+            None
+        };
+
+        stream.get_ref().set_read_timeout(Some(self.config.read_timeout))?;
+        Ok(Player { stream, team_token })
+    }
+
+    // Serve a player past the handshake, with an environment from the pool or a fresh one
+    #[instrument(
+        name = "session",
+        skip_all,
+        fields(peer = ?player.stream.get_ref().peer_addr().ok(), team = ?player.team_token)
+    )]
+    pub async fn serve_session(
+        &self,
+        player: Player,
+        prepared: Option<SuiTF>
+    ) -> Result<(), Box<dyn Error>> {
+        let Player { mut stream, team_token } = player;
+
+        let mut suitf = match prepared {
+            Some(suitf) => suitf,
+            None => match self.prepare_environment().await {
+                Ok(suitf) => suitf,
+                Err((step, e)) => return Err(report_error(&mut stream, self.config.player_errors, step, e)),
+            },
+        };
+
+        if let Some(dir) = &self.config.transcript_dir {
+            let path = transcript_path(dir, stream.get_ref(), team_token.as_deref());
+            match transcript::Recorder::create(&path).and_then(|recorder| suitf.record_transcript(recorder)) {
+                Ok(()) => info!(path = %path.display(), "recording transcript"),
+                Err(e) => warn!(error = %e, "transcript disabled"),
+            }
+        }
+
+        let mut session = Session {
+            suitf,
+            stream,
+            max_upload_size: self.config.max_upload_size,
+            read_timeout: self.config.read_timeout,
+            meter: SessionMeter::new(self.config.limits.clone()),
+            flag: self.config.flag.clone(),
+            team_token,
+            player_errors: self.config.player_errors,
+        };
+
+        if let Err(e) = self.challenge.setup(&mut session).await {
+            return Err(report_error(&mut session.stream, self.config.player_errors, "Challenge setup failed", e));
+        }
+
+        // The player's clock starts once the challenge is ready
+        session.meter = SessionMeter::new(self.config.limits.clone());
+
+        if let Err(e) = self.challenge.solve(&mut session).await {
+            return Err(report_error(&mut session.stream, self.config.player_errors, "Solution failed", e));
+        }
+
+        match self.challenge.check(&mut session).await {
+            Ok(true) => {
+                info!("correct solution");
+                match session.flag() {
+                    Ok(flag) => session.send(&format!("[SERVER] Congrats, flag: {}", flag))?,
+                    Err(e) => {
+                        error!(error = %e, "flag unavailable");
+                        session.send("[SERVER] Flag not found, please contact admin")?
+                    }
+                }
+            }
+            Ok(false) => {
+                info!("incorrect solution");
+                session.send("[SERVER] Solution did not reach the win condition")?;
+            }
+            Err(e) => return Err(report_error(&mut session.stream, self.config.player_errors, "Solution check failed", e)),
+        }
+
+        Ok(())
+    }
+}
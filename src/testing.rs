@@ -0,0 +1,76 @@
+use std::io::{BufRead, Cursor, Read, Write};
+
+use crate::{worker, SuiTF};
+
+// Run `test` against a fresh session with a `solver` account
+pub fn with_session(test: impl FnOnce(&mut SuiTF)) {
+    worker::block_on_local(async {
+        let mut suitf = SuiTF::initialize(Vec::new(), Some(vec!["solver".to_string()])).await.unwrap();
+        test(&mut suitf);
+    })
+    .unwrap();
+}
+
+// Connection for protocol tests: reads come from a script and writes are kept in `sent`.
+// The script can also be written from what was sent, once the first read comes.
+pub struct ScriptedStream {
+    input: Cursor<Vec<u8>>,
+    answer: Option<Box<dyn FnOnce(&str) -> String>>,
+    pub sent: Vec<u8>,
+}
+
+impl ScriptedStream {
+    // Replays `input`
+    pub fn new(input: &str) -> Self {
+        ScriptedStream {
+            input: Cursor::new(input.as_bytes().to_vec()),
+            answer: None,
+            sent: Vec::new(),
+        }
+    }
+
+    // Replays `answer(everything sent before the first read)`
+    pub fn answering(answer: impl FnOnce(&str) -> String + 'static) -> Self {
+        ScriptedStream {
+            input: Cursor::new(Vec::new()),
+            answer: Some(Box::new(answer)),
+            sent: Vec::new(),
+        }
+    }
+
+    pub fn sent_text(&self) -> String {
+        String::from_utf8_lossy(&self.sent).to_string()
+    }
+}
+
+impl Read for ScriptedStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ScriptedStream {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if let Some(answer) = self.answer.take() {
+            self.input = Cursor::new(answer(&self.sent_text()).into_bytes());
+        }
+        self.input.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.input.consume(amt)
+    }
+}
+
+impl Write for ScriptedStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.sent.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use tempfile::NamedTempFile;
+
+use sui_transactional_test_runner::args::{
+    SuiExtraValueArgs, SuiInitArgs, SuiPublishArgs, SuiRunArgs, SuiSubcommand,
+};
+
+use move_core_types::account_address::AccountAddress;
+use move_transactional_test_runner::tasks::{taskify, TaskCommand, TaskInput};
+
+use crate::{output::{self, EventRecord}, transcript::{Operation, Record}, SuiTF};
+
+pub type SuiTaskCommand = TaskCommand<
+    SuiInitArgs,
+    SuiPublishArgs,
+    SuiExtraValueArgs,
+    SuiRunArgs,
+    SuiSubcommand<SuiExtraValueArgs, SuiRunArgs>,
+>;
+
+// Sui transactional test files (`//# init`, `//# publish`, `//# run`, ...), the format of
+// `sui-transactional-test-runner`.
+//
+// `run_file` executes the tasks of such a file against a live session, so a challenge
+// prototyped in the test runner format can serve as the challenge setup. The session
+// is initialized already: an `init` task only checks that its named addresses and
+// accounts exist.
+//
+// `export` turns the history of a session into such a file, to hand out a minimal
+// reproducible case or report an upstream bug. Published modules need their Move
+// sources, searched for in `source_dirs`; packages only available as bytecode, such as
+// uploaded solutions, are left as comments and the tasks after them are numbered
+// differently from the session.
+pub fn export(
+    suitf: &SuiTF,
+    source_dirs: &[PathBuf]
+) -> Result<String, Box<dyn Error>> {
+    let mut out = String::from("// Transactional test exported from a SuiTF session\n");
+    for record in suitf.get_history() {
+        out.push('\n');
+        match export_task(suitf, record, source_dirs) {
+            Ok(task) => out.push_str(&task),
+            Err(e) => out.push_str(&format!("// Task {} ({}) not exported: {}\n", record.seq, record.operation.describe(), e)),
+        }
+    }
+    Ok(out)
+}
+
+pub fn export_to_file(
+    suitf: &SuiTF,
+    path: &Path,
+    source_dirs: &[PathBuf]
+) -> Result<(), Box<dyn Error>> {
+    let text = export(suitf, source_dirs)?;
+    fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn export_task(
+    suitf: &SuiTF,
+    record: &Record,
+    source_dirs: &[PathBuf]
+) -> Result<String, String> {
+    let task = match &record.operation {
+        Operation::Init { named_addresses, accounts, .. } => {
+            let mut line = "//# init --addresses".to_string();
+            for (name, addr) in named_addresses.iter() {
+                line.push_str(&format!(" {}={}", name, addr));
+            }
+            if let Some(accounts) = accounts.as_ref().filter(|a| !a.is_empty()) {
+                line.push_str(&format!(" --accounts {}", accounts.join(" ")));
+            }
+            line.push_str(" --simulator\n");
+            line
+        }
+        Operation::Publish { named_address, modules, dependencies, sender } => {
+            let named_address = named_address.as_deref().ok_or("no named address")?;
+            let names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+            let sources = find_sources(source_dirs, named_address, &names)?;
+
+            let mut line = "//# publish".to_string();
+            if let Some(sender) = sender {
+                line.push_str(&format!(" --sender {}", sender));
+            }
+            if !dependencies.is_empty() {
+                line.push_str(&format!(" --dependencies {}", dependencies.join(" ")));
+            }
+            line.push_str(" --upgradeable\n");
+            for source in sources {
+                line.push_str(source.trim_end());
+                line.push('\n');
+            }
+            line
+        }
+        Operation::Call { package, module, function, args, type_args, sender } => {
+            let mut line = format!("//# run {}::{}::{}", package_name(suitf, package), module, function);
+            if let Some(sender) = sender {
+                line.push_str(&format!(" --sender {}", sender));
+            }
+            if !type_args.is_empty() {
+                let types: Vec<String> = type_args.iter().map(|t| named_type(suitf, t)).collect();
+                line.push_str(&format!(" --type-args {}", types.join(" ")));
+            }
+            if !args.is_empty() {
+                let values: Result<Vec<String>, String> = args.iter().map(|a| render_value(suitf, a)).collect();
+                line.push_str(&format!(" --args {}", values?.join(" ")));
+            }
+            line.push('\n');
+            line
+        }
+        Operation::Ptb { sender, inputs, commands, gas_budget, dry_run } => {
+            let mut line = format!("//# programmable --sender {}", sender);
+            if let Some(budget) = gas_budget {
+                line.push_str(&format!(" --gas-budget {}", budget));
+            }
+            if *dry_run {
+                line.push_str(" --dry-run");
+            }
+            if !inputs.is_empty() {
+                let values: Result<Vec<String>, String> = inputs.iter().map(|i| render_value(suitf, i)).collect();
+                line.push_str(&format!(" --inputs {}", values?.join(" ")));
+            }
+            line.push_str(&format!("\n//> {}\n", commands.join(";\n//> ")));
+            line
+        }
+        Operation::Fund { account, amount, sender } => format!(
+            "//# programmable --sender {} --inputs {} @{}\n//> SplitCoins(Gas, [Input(0)]);\n//> TransferObjects([Result(0)], Input(1))\n",
+            address_name(suitf, sender),
+            amount,
+            account
+        ),
+        Operation::View { object } | Operation::Lookup { object } => {
+            format!("//# view-object {}\n", fake_id(object)?)
+        }
+        Operation::Task { text } => format!("{}\n", text.trim_end()),
+    };
+    Ok(task)
+}
+
+// Source files declaring every module of a package, each file once
+fn find_sources(
+    source_dirs: &[PathBuf],
+    named_address: &str,
+    modules: &[&str]
+) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    for dir in source_dirs {
+        collect_move_files(dir, &mut files);
+    }
+
+    let mut used: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut sources = Vec::new();
+    for module in modules {
+        let declaration = format!("module {}::{}", named_address, module);
+        let found = files.iter().find_map(|path| {
+            let text = fs::read_to_string(path).ok()?;
+            let declares = text.lines().any(|line| {
+                let line = line.trim_start();
+                line.starts_with(&declaration)
+                    && line[declaration.len()..].starts_with(|c: char| c.is_whitespace() || c == '{' || c == ';')
+            });
+            declares.then(|| (path.clone(), text))
+        });
+        match found {
+            Some((path, text)) => {
+                if used.insert(path) {
+                    sources.push(text);
+                }
+            }
+            None => return Err(format!("no source for module {}::{}", named_address, module)),
+        }
+    }
+    Ok(sources)
+}
+
+fn collect_move_files(
+    dir: &Path,
+    files: &mut Vec<PathBuf>
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_move_files(&path, files);
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("move") {
+            files.push(path);
+        }
+    }
+}
+
+fn package_name(
+    suitf: &SuiTF,
+    package: &str
+) -> String {
+    AccountAddress::from_hex_literal(package)
+        .ok()
+        .and_then(|addr| suitf.get_package_name(&addr))
+        .unwrap_or_else(|| package.to_string())
+}
+
+fn address_name(
+    suitf: &SuiTF,
+    text: &str
+) -> String {
+    match AccountAddress::from_hex_literal(text) {
+        Ok(addr) => suitf
+            .get_account_name(&addr)
+            .or_else(|| suitf.get_package_name(&addr))
+            .unwrap_or_else(|| addr.to_hex_literal()),
+        Err(_) => text.to_string(),
+    }
+}
+
+// Package addresses in a type back to package names
+fn named_type(
+    suitf: &SuiTF,
+    type_: &str
+) -> String {
+    let mut result = String::new();
+    let mut token = String::new();
+    let flush = |token: &mut String, result: &mut String| {
+        match token.split_once("::") {
+            Some((head, rest)) if head.starts_with("0x") => {
+                result.push_str(&format!("{}::{}", package_name(suitf, head), rest));
+            }
+            _ => result.push_str(token),
+        }
+        token.clear();
+    };
+
+    for c in type_.chars() {
+        if matches!(c, '<' | '>' | ',' | ' ') {
+            flush(&mut token, &mut result);
+            result.push(c);
+        } else {
+            token.push(c);
+        }
+    }
+    flush(&mut token, &mut result);
+    result
+}
+
+fn fake_id(value: &Value) -> Result<String, String> {
+    match value.as_array().map(|a| a.as_slice()) {
+        Some([task, idx]) => Ok(format!("{},{}", task, idx)),
+        _ => Err(format!("object {} has no task enumeration", value)),
+    }
+}
+
+// JSON-lines argument (see `jsonl::parse_value`) in the test runner's value syntax
+fn render_value(
+    suitf: &SuiTF,
+    value: &Value
+) -> Result<String, String> {
+    let number = |v: &Value| v.as_str().map(|s| s.to_string()).unwrap_or_else(|| v.to_string());
+    let rendered = match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => format!("{}u64", n),
+        Value::String(s) => format!("@{}", address_name(suitf, s)),
+        Value::Object(map) if map.len() == 1 => {
+            let (kind, inner) = map.iter().next().unwrap();
+            match kind.as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "u256" => format!("{}{}", number(inner), kind),
+                "number" => number(inner),
+                "bool" => inner.to_string(),
+                "address" => format!("@{}", address_name(suitf, inner.as_str().ok_or("bad address")?)),
+                "string" => {
+                    let text = inner.as_str().ok_or("bad string")?;
+                    format!("x\"{}\"", text.bytes().map(|b| format!("{:02x}", b)).collect::<String>())
+                }
+                "vector" => {
+                    let elems = inner.as_array().ok_or("bad vector")?;
+                    let rendered: Result<Vec<String>, String> = elems.iter().map(|e| render_value(suitf, e)).collect();
+                    format!("vector[{}]", rendered?.join(", "))
+                }
+                "object" => format!("object({})", fake_id(inner)?),
+                "imm_shared" => format!("immshared({})", fake_id(inner)?),
+                "receiving" => format!("receiving({})", fake_id(inner)?),
+                _ => return Err(format!("unsupported argument {}", value)),
+            }
+        }
+        _ => return Err(format!("unsupported argument {}", value)),
+    };
+    Ok(rendered)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+// Outcome of one task of a file
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskResult {
+    pub number: usize,
+    pub name: String,
+    // Line of the task command in the file
+    pub line: usize,
+    pub status: TaskStatus,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    // Object changes by kind (`created`, `mutated`, ...)
+    pub changes: BTreeMap<String, Vec<(u64, u64)>>,
+    pub events: Vec<EventRecord>,
+    pub gas_used: u64,
+}
+
+impl TaskResult {
+    fn new(task: &TaskInput<SuiTaskCommand>) -> Self {
+        TaskResult {
+            number: task.number,
+            name: task.name.clone(),
+            line: task.start_line,
+            status: TaskStatus::Ok,
+            output: None,
+            error: None,
+            changes: BTreeMap::new(),
+            events: Vec::new(),
+            gas_used: 0,
+        }
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<Vec<TaskInput<SuiTaskCommand>>, Box<dyn Error>> {
+    taskify::<SuiTaskCommand>(path)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e).into())
+}
+
+pub fn parse_source(text: &str) -> Result<Vec<TaskInput<SuiTaskCommand>>, Box<dyn Error>> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+    parse_file(file.path())
+}
+
+// Text of a single task, as found in a file
+pub fn task_source(
+    task_text: &str,
+    data: Option<&str>
+) -> String {
+    let command = task_text.trim();
+    let mut text = if command.starts_with("//#") {
+        command.to_string()
+    } else {
+        format!("//# {}", command)
+    };
+    if let Some(data) = data.map(|d| d.trim_matches('\n')).filter(|d| !d.is_empty()) {
+        text.push('\n');
+        text.push_str(data);
+    }
+    text
+}
+
+// Run every task of a file, failed tasks do not stop the following ones
+pub async fn run_file(
+    suitf: &mut SuiTF,
+    path: &Path
+) -> Result<Vec<TaskResult>, Box<dyn Error>> {
+    run_tasks(suitf, parse_file(path)?).await
+}
+
+pub async fn run_source(
+    suitf: &mut SuiTF,
+    text: &str
+) -> Result<Vec<TaskResult>, Box<dyn Error>> {
+    run_tasks(suitf, parse_source(text)?).await
+}
+
+async fn run_tasks(
+    suitf: &mut SuiTF,
+    tasks: Vec<TaskInput<SuiTaskCommand>>
+) -> Result<Vec<TaskResult>, Box<dyn Error>> {
+    let mut results = Vec::new();
+    for task in tasks {
+        let mut result = TaskResult::new(&task);
+
+        if let TaskCommand::Init(init, args) = &task.command {
+            let mut missing: Vec<String> = init.named_addresses
+                .iter()
+                .map(|(name, _)| name.clone())
+                .filter(|name| !suitf.has_named_address(name))
+                .collect();
+            missing.extend(args.accounts.iter().flatten().filter(|a| suitf.get_account_address(a).is_none()).cloned());
+
+            if missing.is_empty() {
+                result.status = TaskStatus::Skipped;
+            } else {
+                result.status = TaskStatus::Failed;
+                result.error = Some(format!("The session has no named address or account: {}", missing.join(", ")));
+            }
+            results.push(result);
+            continue;
+        }
+
+        let events_before = suitf.get_events().len();
+        let gas_before = suitf.get_gas_used();
+        match suitf.run_task(task).await {
+            Ok(out) => {
+                let text = out.as_deref().unwrap_or_default();
+                result.changes = output::parse_object_changes(text).into_iter().collect();
+                result.output = out;
+            }
+            Err(e) => {
+                result.status = TaskStatus::Failed;
+                result.error = Some(e.to_string());
+            }
+        }
+        result.events = suitf.get_events()[events_before..].to_vec();
+        result.gas_used = suitf.get_gas_used().saturating_sub(gas_before);
+        results.push(result);
+    }
+    Ok(results)
+}
@@ -0,0 +1,396 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use sui_transactional_test_runner::args::SuiValue;
+
+use move_compiler::shared::NumericalAddress;
+use move_core_types::{account_address::AccountAddress, language_storage::TypeTag};
+use move_transactional_test_runner::framework::MaybeNamedCompiledModule;
+
+use crate::{jsonl, package, transactional, FrameworkSource, SuiTF};
+
+// Session transcripts: one JSON line per operation run through `SuiTF`, with a hash
+// of what it produced.
+//
+//   {"seq":0,"op":"init","named_addresses":[["challenge","0x0"]],"accounts":["solver"],"result":{...}}
+//   {"seq":1,"op":"publish","modules":[{"name":"pool","sha256":"..","bytes":".."}],...,"result":{...}}
+//   {"seq":2,"op":"call","package":"0x..","module":"pool","function":"swap","args":[...],...}
+//
+// Arguments use the JSON-lines syntax (see `jsonl::parse_value`), so a transcript can be
+// replayed with `replay` to find where a session stops behaving the same.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub operation: Operation,
+    pub result: Outcome,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    Init {
+        named_addresses: Vec<(String, String)>,
+        accounts: Option<Vec<String>>,
+        // Directory of a local framework checkout, the bundled framework when absent
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        framework: Option<PathBuf>,
+    },
+    Publish {
+        named_address: Option<String>,
+        modules: Vec<RecordedModule>,
+        dependencies: Vec<String>,
+        sender: Option<String>,
+    },
+    Call {
+        package: String,
+        module: String,
+        function: String,
+        args: Vec<Value>,
+        type_args: Vec<String>,
+        sender: Option<String>,
+    },
+    Ptb {
+        sender: String,
+        inputs: Vec<Value>,
+        commands: Vec<String>,
+        gas_budget: Option<u64>,
+        dry_run: bool,
+    },
+    Fund {
+        account: String,
+        amount: u64,
+        sender: String,
+    },
+    View {
+        object: Value,
+    },
+    // View made by the framework itself, see `SuiTF::lookup_object`
+    Lookup {
+        object: Value,
+    },
+    // Task of a transactional test file, command line and data
+    Task {
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedModule {
+    pub name: String,
+    pub sha256: String,
+    // Base64 of the module bytecode
+    pub bytes: String,
+}
+
+// What an operation produced: whether it succeeded, and the SHA-256 of the adapter's
+// output or error text. This is not the transaction effects digest, which the adapter
+// does not report: outcomes with the same text compare equal even if their effects
+// differ in ways the text leaves out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub ok: bool,
+    pub output_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    pub fn from_result<E: ToString>(result: &Result<Option<String>, E>) -> Self {
+        match result {
+            Ok(output) => Outcome {
+                ok: true,
+                output_hash: sha256_hex(output.as_deref().unwrap_or_default().as_bytes()),
+                error: None,
+            },
+            Err(e) => {
+                let error = e.to_string();
+                Outcome {
+                    ok: false,
+                    output_hash: sha256_hex(error.as_bytes()),
+                    error: Some(error),
+                }
+            }
+        }
+    }
+}
+
+impl Operation {
+    pub fn init(
+        named_addresses: &[(String, NumericalAddress)],
+        accounts: &Option<Vec<String>>,
+        framework: &FrameworkSource
+    ) -> Self {
+        Operation::Init {
+            named_addresses: named_addresses
+                .iter()
+                .map(|(name, addr)| (name.clone(), addr.into_inner().to_hex_literal()))
+                .collect(),
+            accounts: accounts.clone(),
+            framework: match framework {
+                FrameworkSource::Bundled => None,
+                FrameworkSource::Local(dir) => Some(dir.clone()),
+            },
+        }
+    }
+
+    pub fn publish(
+        modules: &[MaybeNamedCompiledModule],
+        dependencies: &[String],
+        sender: &Option<String>
+    ) -> Self {
+        let recorded = modules.iter().map(|m| {
+            let mut bytes = Vec::new();
+            if let Err(e) = m.module.serialize_with_version(m.module.version, &mut bytes) {
+                warn!(module = %m.module.name(), error = %e, "failed to serialize module for the transcript");
+            }
+            RecordedModule {
+                name: m.module.name().to_string(),
+                sha256: sha256_hex(&bytes),
+                bytes: BASE64.encode(&bytes),
+            }
+        }).collect();
+
+        Operation::Publish {
+            named_address: modules.first().and_then(|m| m.named_address).map(|n| n.to_string()),
+            modules: recorded,
+            dependencies: dependencies.to_vec(),
+            sender: sender.clone(),
+        }
+    }
+
+    pub fn call(
+        package: AccountAddress,
+        module: &str,
+        function: &str,
+        args: &[SuiValue],
+        type_args: &[TypeTag],
+        sender: &Option<String>
+    ) -> Self {
+        Operation::Call {
+            package: package.to_hex_literal(),
+            module: module.to_string(),
+            function: function.to_string(),
+            // Unrepresentable arguments are kept as `null`, replaying them fails
+            args: args.iter().map(|v| jsonl::sui_value_json(v).unwrap_or(Value::Null)).collect(),
+            type_args: type_args.iter().map(|t| t.to_string()).collect(),
+            sender: sender.clone(),
+        }
+    }
+
+    // Short description for divergence reports
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Init { .. } => "init".to_string(),
+            Operation::Publish { named_address, modules, .. } => format!(
+                "publish {} ({})",
+                named_address.as_deref().unwrap_or("<unnamed>"),
+                modules.iter().map(|m| m.name.as_str()).collect::<Vec<_>>().join(", ")
+            ),
+            Operation::Call { package, module, function, .. } => format!("call {}::{}::{}", package, module, function),
+            Operation::Ptb { sender, commands, .. } => format!("ptb from {}: {}", sender, commands.join("; ")),
+            Operation::Fund { account, amount, .. } => format!("fund {} with {}", account, amount),
+            Operation::View { object } => format!("view {}", object),
+            Operation::Lookup { object } => format!("lookup {}", object),
+            Operation::Task { text } => format!("task {}", text.lines().next().unwrap_or_default()),
+        }
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Writes records as they happen, attached to a `SuiTF` with `SuiTF::record_transcript`
+pub struct Recorder {
+    writer: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Recorder { writer }
+    }
+
+    pub fn create(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create transcript {}: {}", path.display(), e))?;
+        Ok(Recorder::new(Box::new(BufWriter::new(file))))
+    }
+
+    pub fn record(
+        &mut self,
+        record: &Record
+    ) -> Result<(), Box<dyn Error>> {
+        let line = serde_json::to_string(record)?;
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<Record>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read transcript {}: {}", path.display(), e))?;
+    let mut records = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(line)
+            .map_err(|e| format!("Invalid transcript line {}: {}", number + 1, e))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// An operation whose outcome differs from the recorded one
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub seq: u64,
+    pub operation: String,
+    pub expected: Outcome,
+    pub actual: Outcome,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub operations: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    pub fn is_identical(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+pub async fn replay(path: &Path) -> Result<ReplayReport, Box<dyn Error>> {
+    replay_records(&read(path)?).await
+}
+
+// Re-run recorded operations in a fresh environment and compare their outcomes.
+// Operations keep running after a divergence, so the report shows every one.
+pub async fn replay_records(records: &[Record]) -> Result<ReplayReport, Box<dyn Error>> {
+    let (_, report) = rebuild(records).await?;
+    Ok(report)
+}
+
+// Fresh environment with the recorded operations re-run, and how their outcomes compare.
+// The environment uses the framework recorded at initialization.
+pub async fn rebuild(records: &[Record]) -> Result<(SuiTF, ReplayReport), Box<dyn Error>> {
+    let (first, rest) = records.split_first().ok_or("Empty transcript")?;
+    let (named_addresses, accounts, framework) = match &first.operation {
+        Operation::Init { named_addresses, accounts, framework } => (named_addresses, accounts, framework),
+        _ => return Err("Transcript does not start with init".into()),
+    };
+    let framework = match framework {
+        Some(dir) => FrameworkSource::Local(dir.clone()),
+        None => FrameworkSource::Bundled,
+    };
+
+    let mut parsed_addresses = Vec::new();
+    for (name, addr) in named_addresses.iter() {
+        parsed_addresses.push((name.clone(), NumericalAddress::parse_str(addr)?));
+    }
+    let mut suitf = SuiTF::initialize_with_framework(parsed_addresses, accounts.clone(), &framework).await?;
+
+    let mut report = ReplayReport::default();
+    compare(&mut report, first, suitf.init_outcome().clone());
+
+    for record in rest {
+        let before = suitf.get_history().len();
+        let result = execute(&mut suitf, &record.operation).await;
+        // Compared as the environment recorded it, which is the text the session recorded:
+        // returned errors may differ from it, e.g. aborts come back as `abort::MoveAbort`.
+        // Operations that failed before reaching the adapter record nothing.
+        let actual = match suitf.get_history() {
+            history if history.len() == before + 1 => history[before].result.clone(),
+            _ => Outcome::from_result(&result),
+        };
+        compare(&mut report, record, actual);
+    }
+    Ok((suitf, report))
+}
+
+fn compare(
+    report: &mut ReplayReport,
+    record: &Record,
+    actual: Outcome
+) {
+    report.operations += 1;
+    if actual.ok != record.result.ok || actual.output_hash != record.result.output_hash {
+        let operation = record.operation.describe();
+        warn!(seq = record.seq, operation = %operation, "replay diverged");
+        report.divergences.push(Divergence {
+            seq: record.seq,
+            operation,
+            expected: record.result.clone(),
+            actual,
+        });
+    }
+}
+
+async fn execute(
+    suitf: &mut SuiTF,
+    operation: &Operation
+) -> Result<Option<String>, Box<dyn Error>> {
+    match operation {
+        Operation::Init { .. } => Err("Unexpected init in the middle of a transcript".into()),
+        Operation::Publish { named_address, modules, dependencies, sender } => {
+            let named_address = named_address.as_deref().ok_or("Recorded publish has no named address")?;
+            let mut compiled = Vec::new();
+            for module in modules.iter() {
+                let bytes = BASE64.decode(&module.bytes)
+                    .map_err(|e| format!("Bad module bytes for {}: {}", module.name, e))?;
+                compiled.push(package::deserialize_module(&bytes, named_address)?);
+            }
+            let addr = suitf.publish_compiled_module(compiled, dependencies.clone(), sender.clone()).await?;
+            Ok(Some(addr.to_hex_literal()))
+        }
+        Operation::Call { package, module, function, args, type_args, sender } => {
+            let package = jsonl::parse_address(suitf, package)?;
+            let args = jsonl::parse_args(suitf, args)?;
+            let mut tags = Vec::new();
+            for t in type_args.iter() {
+                tags.push(jsonl::parse_type_tag(suitf, t)?);
+            }
+            suitf.call_function(package, module, function, args, tags, sender.clone()).await
+        }
+        Operation::Ptb { sender, inputs, commands, gas_budget, dry_run } => {
+            let mut parsed = Vec::new();
+            for input in inputs.iter() {
+                parsed.push(jsonl::parse_value(suitf, input)?);
+            }
+            suitf.programmable_transaction(sender.clone(), parsed, commands.clone(), *gas_budget, *dry_run).await
+        }
+        Operation::Fund { account, amount, sender } => {
+            let sender = jsonl::parse_address(suitf, sender)?;
+            suitf.fund_account(account.clone(), *amount, sender).await?;
+            Ok(None)
+        }
+        Operation::View { object } => {
+            let id = jsonl::parse_object_id(suitf, object)?;
+            suitf.view_object_raw(id).await
+        }
+        Operation::Lookup { object } => {
+            let id = jsonl::parse_object_id(suitf, object)?;
+            suitf.lookup_object(id).await
+        }
+        Operation::Task { text } => {
+            let mut tasks = transactional::parse_source(text)?;
+            if tasks.len() != 1 {
+                return Err(format!("Recorded task holds {} tasks", tasks.len()).into());
+            }
+            suitf.run_task(tasks.remove(0)).await
+        }
+    }
+}
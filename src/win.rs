@@ -0,0 +1,996 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use sui_transactional_test_runner::args::SuiExtraValueArgs;
+
+use move_core_types::{account_address::AccountAddress, parsing::values::ParsedValue, u256::U256};
+
+use crate::{abort::MoveAbort, jsonl, manifest::CallSpec, output, rpc, SuiTF};
+
+// Win conditions, evaluated against the state of a session once the player is done.
+// In a manifest they go in a `[win]` table, tagged by `kind`:
+//
+//   [win]
+//   kind = "all"
+//   conditions = [
+//       { kind = "owns_object", owner = "solver", object_type = "challenge::flag::Flag" },
+//       { kind = "balance", account = "solver", op = "ge", amount = 1000000000 },
+//       { kind = "not", condition = { kind = "event", event_type = "challenge::pool::Paused" } },
+//   ]
+//
+// Objects use the JSON-lines syntax, `[task, index]`, a hex id or an alias
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WinCondition {
+    // Field of an object's contents, nested fields separated by dots (`balance.value`)
+    ObjectField {
+        object: Value,
+        field: String,
+        #[serde(default)]
+        op: CompareOp,
+        value: Value,
+    },
+    // Total of an account's coins of `coin_type`
+    Balance {
+        account: String,
+        #[serde(default = "default_coin_type")]
+        coin_type: String,
+        #[serde(default = "default_balance_op")]
+        op: CompareOp,
+        amount: u64,
+    },
+    // An event of `event_type` was emitted, with the given field values
+    Event {
+        event_type: String,
+        #[serde(default)]
+        fields: BTreeMap<String, Value>,
+    },
+    // `owner` owns `object`, or any object of `object_type`
+    OwnsObject {
+        owner: String,
+        object: Option<Value>,
+        object_type: Option<String>,
+    },
+    // `object` has a dynamic field keyed by `key`, with a value compared to `value` when
+    // given. The fields of a `Table`, `Bag`, ... in the object are read with `field`
+    // naming it (`balances`), see `dynamic`.
+    DynamicField {
+        object: Value,
+        field: Option<String>,
+        key: Value,
+        #[serde(default)]
+        op: CompareOp,
+        value: Option<Value>,
+    },
+    // A function returning `true`, run in a dry-run programmable transaction, see `view`
+    View {
+        call: CallSpec,
+    },
+    All {
+        conditions: Vec<WinCondition>,
+    },
+    Any {
+        conditions: Vec<WinCondition>,
+    },
+    Not {
+        condition: Box<WinCondition>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareOp {
+    #[default]
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+fn default_coin_type() -> String {
+    "0x2::sui::SUI".to_string()
+}
+
+fn default_balance_op() -> CompareOp {
+    CompareOp::Ge
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl CompareOp {
+    // Numbers compare by value, addresses by address, anything else only for (in)equality
+    pub fn compare(
+        &self,
+        actual: &str,
+        expected: &str
+    ) -> Result<bool, String> {
+        if let (Ok(a), Ok(e)) = (actual.parse::<u128>(), expected.parse::<u128>()) {
+            return Ok(self.holds(a.cmp(&e)));
+        }
+
+        let equal = match (AccountAddress::from_hex_literal(actual), AccountAddress::from_hex_literal(expected)) {
+            (Ok(a), Ok(e)) => a == e,
+            _ => actual == expected,
+        };
+        match self {
+            CompareOp::Eq => Ok(equal),
+            CompareOp::Ne => Ok(!equal),
+            _ => Err(format!("Cannot compare '{}' {} '{}', only numbers are ordered", actual, self, expected)),
+        }
+    }
+
+    fn holds(
+        &self,
+        ordering: std::cmp::Ordering
+    ) -> bool {
+        match self {
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::Ne => ordering.is_ne(),
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::Le => ordering.is_le(),
+            CompareOp::Gt => ordering.is_gt(),
+            CompareOp::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+impl fmt::Display for WinCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WinCondition::ObjectField { object, field, op, value } => {
+                write!(f, "field '{}' of object {} {} {}", field, object, op, value_text(value))
+            }
+            WinCondition::Balance { account, coin_type, op, amount } => {
+                write!(f, "balance of {} in {} {} {}", account, coin_type, op, amount)
+            }
+            WinCondition::Event { event_type, fields } if fields.is_empty() => {
+                write!(f, "event {} emitted", event_type)
+            }
+            WinCondition::Event { event_type, fields } => {
+                write!(f, "event {} emitted with {}", event_type, json!(fields))
+            }
+            WinCondition::OwnsObject { owner, object: Some(object), .. } => {
+                write!(f, "{} owns object {}", owner, object)
+            }
+            WinCondition::OwnsObject { owner, object_type, .. } => {
+                write!(f, "{} owns a {}", owner, object_type.as_deref().unwrap_or("<any type>"))
+            }
+            WinCondition::DynamicField { object, field, key, op, value } => {
+                match field {
+                    Some(field) => write!(f, "'{}' of object {} has key {}", field, object, value_text(key))?,
+                    None => write!(f, "object {} has dynamic field {}", object, value_text(key))?,
+                }
+                match value {
+                    Some(value) => write!(f, " with value {} {}", op, value_text(value)),
+                    None => Ok(()),
+                }
+            }
+            WinCondition::View { call } => {
+                write!(f, "{}::{} returns true", call.package, call.function)
+            }
+            WinCondition::All { conditions } => write!(f, "all of ({})", join(conditions)),
+            WinCondition::Any { conditions } => write!(f, "any of ({})", join(conditions)),
+            WinCondition::Not { condition } => write!(f, "not ({})", condition),
+        }
+    }
+}
+
+fn join(conditions: &[WinCondition]) -> String {
+    conditions.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+// Why a condition is not met: it was checked and does not hold, or it could not be
+// checked at all (unknown object or account, bad comparison, failed call). `not` only
+// negates the former.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unmet {
+    Fails(String),
+    Error(String),
+}
+
+impl fmt::Display for Unmet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unmet::Fails(reason) | Unmet::Error(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Unmet {}
+
+fn reasons(unmet: &[Unmet]) -> String {
+    unmet.iter().map(|u| u.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+impl WinCondition {
+    // `Ok` when the condition holds, otherwise why it is not met
+    pub fn evaluate<'a>(
+        &'a self,
+        suitf: &'a mut SuiTF
+    ) -> Pin<Box<dyn Future<Output = Result<(), Unmet>> + 'a>> {
+        Box::pin(async move {
+            match self {
+                WinCondition::ObjectField { object, field, op, value } => {
+                    object_field(suitf, object, field, *op, value).await
+                }
+                WinCondition::Balance { account, coin_type, op, amount } => {
+                    balance(suitf, account, coin_type, *op, *amount).await
+                }
+                WinCondition::Event { event_type, fields } => event(suitf, event_type, fields),
+                WinCondition::OwnsObject { owner, object, object_type } => {
+                    owns_object(suitf, owner, object.as_ref(), object_type.as_deref()).await
+                }
+                WinCondition::DynamicField { object, field, key, op, value } => {
+                    dynamic_field(suitf, object, field.as_deref(), key, *op, value.as_ref()).await
+                }
+                WinCondition::View { call } => view(suitf, call).await,
+                // One condition that does not hold settles `all`, whatever the errors of the others
+                WinCondition::All { conditions } => {
+                    let mut unmet = Vec::new();
+                    for condition in conditions.iter() {
+                        if let Err(reason) = condition.evaluate(suitf).await {
+                            unmet.push(reason);
+                        }
+                    }
+                    if unmet.is_empty() {
+                        Ok(())
+                    } else if unmet.iter().any(|u| matches!(u, Unmet::Fails(_))) {
+                        Err(Unmet::Fails(reasons(&unmet)))
+                    } else {
+                        Err(Unmet::Error(reasons(&unmet)))
+                    }
+                }
+                // Only conditions that all do not hold settle `any`
+                WinCondition::Any { conditions } => {
+                    let mut unmet = Vec::new();
+                    for condition in conditions.iter() {
+                        match condition.evaluate(suitf).await {
+                            Ok(()) => return Ok(()),
+                            Err(reason) => unmet.push(reason),
+                        }
+                    }
+                    if unmet.iter().all(|u| matches!(u, Unmet::Fails(_))) {
+                        Err(Unmet::Fails(format!("none of the conditions hold: {}", reasons(&unmet))))
+                    } else {
+                        Err(Unmet::Error(reasons(&unmet)))
+                    }
+                }
+                WinCondition::Not { condition } => match condition.evaluate(suitf).await {
+                    Ok(()) => Err(Unmet::Fails(format!("{} holds", condition))),
+                    Err(Unmet::Fails(_)) => Ok(()),
+                    Err(err) => Err(err),
+                },
+            }
+        })
+    }
+}
+
+async fn object_field(
+    suitf: &mut SuiTF,
+    object: &Value,
+    field: &str,
+    op: CompareOp,
+    expected: &Value
+) -> Result<(), Unmet> {
+    let id = jsonl::parse_object_id(suitf, object).map_err(|e| Unmet::Error(e.to_string()))?;
+    let raw = suitf
+        .lookup_object(id)
+        .await
+        .map_err(|e| Unmet::Error(format!("object {} cannot be viewed: {}", object, e)))?
+        .ok_or_else(|| Unmet::Fails(format!("object {} does not exist", object)))?;
+
+    let pointer = format!("/Contents/{}", field.replace('.', "/"));
+    let actual = SuiTF::parse_output(&raw)
+        .pointer(&pointer)
+        .map(value_text)
+        .ok_or_else(|| Unmet::Error(format!("object {} has no field '{}'", object, field)))?;
+
+    let expected = value_text(expected);
+    if op.compare(&actual, &expected).map_err(Unmet::Error)? {
+        Ok(())
+    } else {
+        Err(Unmet::Fails(format!("field '{}' of object {} is {}, expected {} {}", field, object, actual, op, expected)))
+    }
+}
+
+async fn balance(
+    suitf: &mut SuiTF,
+    account: &str,
+    coin_type: &str,
+    op: CompareOp,
+    amount: u64
+) -> Result<(), Unmet> {
+    let owner = jsonl::parse_address(suitf, account).map_err(|e| Unmet::Error(e.to_string()))?;
+    let coin_type = jsonl::resolve_type_names(suitf, coin_type);
+    let total = suitf.get_balance(owner, &coin_type).await.map_err(|e| Unmet::Error(e.to_string()))?;
+
+    if op.compare(&total.to_string(), &amount.to_string()).map_err(Unmet::Error)? {
+        Ok(())
+    } else {
+        Err(Unmet::Fails(format!("{} holds {} of {}, expected {} {}", account, total, coin_type, op, amount)))
+    }
+}
+
+fn event(
+    suitf: &SuiTF,
+    event_type: &str,
+    fields: &BTreeMap<String, Value>
+) -> Result<(), Unmet> {
+    let matching: Vec<&output::EventRecord> = suitf
+        .get_events()
+        .iter()
+        .filter(|e| output::type_matches(suitf, &e.type_, event_type))
+        .collect();
+    if matching.is_empty() {
+        return Err(Unmet::Fails(format!("no {} event was emitted", event_type)));
+    }
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    let mut mismatch = String::new();
+    for record in matching.iter() {
+        let decoded = decode_event(suitf, record)
+            .map_err(|e| Unmet::Error(format!("{} event could not be decoded: {}", event_type, e)))?;
+
+        let found = fields.iter().find_map(|(name, expected)| {
+            let actual = decoded.get(name).map(value_text);
+            match actual {
+                Some(actual) if actual == value_text(expected) => None,
+                Some(actual) => Some(format!("'{}' is {}, expected {}", name, actual, value_text(expected))),
+                None => Some(format!("no field '{}'", name)),
+            }
+        });
+        match found {
+            None => return Ok(()),
+            Some(reason) => mismatch = reason,
+        }
+    }
+
+    Err(Unmet::Fails(format!(
+        "{} {} event(s) emitted, none with matching fields (last: {})",
+        matching.len(),
+        event_type,
+        mismatch
+    )))
+}
+
+async fn owns_object(
+    suitf: &mut SuiTF,
+    owner: &str,
+    object: Option<&Value>,
+    object_type: Option<&str>
+) -> Result<(), Unmet> {
+    let address = jsonl::parse_address(suitf, owner).map_err(|e| Unmet::Error(e.to_string()))?;
+    let owner_hex = address.to_hex_literal();
+    let owner_name = suitf.get_account_name(&address);
+    let object_type = object_type.map(|t| jsonl::resolve_type_names(suitf, t));
+
+    let candidates = match object {
+        Some(object) => vec![jsonl::parse_object_id(suitf, object).map_err(|e| Unmet::Error(e.to_string()))?],
+        None if object_type.is_some() => suitf.get_known_objects(),
+        None => return Err(Unmet::Error("owns_object needs an object or an object_type".to_string())),
+    };
+
+    let mut last_owner = None;
+    for id in candidates {
+        let raw = match suitf.lookup_object(id).await {
+            Ok(Some(raw)) => raw,
+            _ => continue,
+        };
+
+        if let Some(expected) = object_type.as_deref() {
+            let type_ok = output::parse_object_type(&raw)
+                .map(|t| output::type_matches(suitf, &t, expected))
+                .unwrap_or(false);
+            if !type_ok {
+                continue;
+            }
+        }
+
+        match output::parse_owner_account(&raw) {
+            Some(addr) if addr == owner_hex || Some(&addr) == owner_name.as_ref() => return Ok(()),
+            _ => last_owner = output::parse_object_owner(&raw),
+        }
+    }
+
+    let what = match (object, object_type.as_deref()) {
+        (Some(object), _) => format!("object {}", object),
+        (None, Some(t)) => format!("a {}", t),
+        (None, None) => unreachable!(),
+    };
+    match last_owner {
+        Some(other) => Err(Unmet::Fails(format!("{} does not own {}, owner is {}", owner, what, other))),
+        None => Err(Unmet::Fails(format!("{} does not own {}", owner, what))),
+    }
+}
+
+async fn dynamic_field(
+    suitf: &mut SuiTF,
+    object: &Value,
+    field: Option<&str>,
+    key: &Value,
+    op: CompareOp,
+    expected: Option<&Value>
+) -> Result<(), Unmet> {
+    let id = jsonl::parse_object_id(suitf, object).map_err(|e| Unmet::Error(e.to_string()))?;
+    let entry = suitf
+        .dynamic_field(id, field, key)
+        .await
+        .map_err(|e| Unmet::Error(format!("fields of object {} cannot be read: {}", object, e)))?
+        .ok_or_else(|| Unmet::Fails(format!("object {} has no dynamic field {}", object, value_text(key))))?;
+
+    let expected = match expected {
+        Some(expected) => decode_contents(suitf, expected, &entry.value_type),
+        None => return Ok(()),
+    };
+    let actual = value_text(&entry.value);
+    if op.compare(&actual, &value_text(&expected)).map_err(Unmet::Error)? {
+        Ok(())
+    } else {
+        Err(Unmet::Fails(format!("dynamic field {} is {}, expected {} {}", value_text(key), actual, op, value_text(&expected))))
+    }
+}
+
+// The adapter does not report the values calls return, so the view runs in a dry-run
+// programmable transaction that passes its result to `vec_set::insert` on a set already
+// holding `false`: the transaction aborts there exactly when the view returns `false`.
+// A view that does not return a `bool` fails to build the transaction.
+async fn view(
+    suitf: &mut SuiTF,
+    call: &CallSpec
+) -> Result<(), Unmet> {
+    let name = format!("{}::{}", call.package, call.function);
+    let (inputs, commands) = view_transaction(suitf, call).map_err(|e| Unmet::Error(format!("{}: {}", name, e)))?;
+    let sender = call.sender.clone().unwrap_or_else(|| "solver".to_string());
+
+    match suitf.programmable_transaction(sender, inputs, commands, None, true).await {
+        Ok(_) => Ok(()),
+        Err(e) => match e.downcast_ref::<MoveAbort>() {
+            Some(abort) if abort.address == Some(AccountAddress::TWO) && abort.module == "vec_set" => {
+                Err(Unmet::Fails(format!("{} returned false", name)))
+            }
+            // An abort is the view's answer, any other failure means it did not run
+            Some(abort) => Err(Unmet::Fails(format!("{} failed: {}", name, abort))),
+            None => Err(Unmet::Error(format!("{} failed: {}", name, e))),
+        },
+    }
+}
+
+// Inputs and `//>` commands of the transaction running `call` for `view`
+fn view_transaction(
+    suitf: &SuiTF,
+    call: &CallSpec
+) -> Result<(Vec<ParsedValue<SuiExtraValueArgs>>, Vec<String>), Box<dyn std::error::Error>> {
+    let package = jsonl::parse_address(suitf, &call.package)?;
+    let module = match &call.module {
+        Some(module) => module.clone(),
+        None => jsonl::find_module(suitf, package, &call.function)?,
+    };
+    let mut type_args = Vec::new();
+    for t in call.type_args.iter() {
+        type_args.push(jsonl::parse_type_tag(suitf, t)?.to_canonical_string(true));
+    }
+
+    let mut inputs = Vec::new();
+    for arg in call.args.iter() {
+        inputs.push(jsonl::parse_value(suitf, arg)?);
+    }
+    let args: Vec<String> = (0..inputs.len()).map(|i| format!("Input({})", i)).collect();
+    inputs.push(jsonl::parse_value(suitf, &json!({ "bool": false }))?);
+
+    let type_args = if type_args.is_empty() { String::new() } else { format!("<{}>", type_args.join(", ")) };
+    let commands = vec![
+        format!("{}::{}::{}{}({})", package.to_hex_literal(), module, call.function, type_args, args.join(", ")),
+        format!("0x2::vec_set::singleton<bool>(Input({}))", inputs.len() - 1),
+        "0x2::vec_set::insert<bool>(Result(1), Result(0))".to_string(),
+    ];
+    Ok((inputs, commands))
+}
+
+// Field values print as text in object contents, numbers as strings in decoded events
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.trim().trim_end_matches(',').trim_matches('"').to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Decode an event's BCS contents to JSON, using the layout of its type in the
+// published packages
+pub fn decode_event(
+    suitf: &SuiTF,
+    event: &output::EventRecord
+) -> Result<Value, String> {
+    let mut pos = 0;
+    let value = decode_value(suitf, &event.contents, &mut pos, &event.type_)?;
+    if pos != event.contents.len() {
+        return Err(format!("Event contents do not match type {}", event.type_));
+    }
+    Ok(value)
+}
+
+fn decode_value(
+    suitf: &SuiTF,
+    bytes: &[u8],
+    pos: &mut usize,
+    type_: &str
+) -> Result<Value, String> {
+    let type_ = type_.trim();
+    let value = match type_ {
+        "bool" => json!(rpc::take(bytes, pos, 1)?[0] != 0),
+        "u8" => json!(rpc::take(bytes, pos, 1)?[0]),
+        "u16" => json!(u16::from_le_bytes(rpc::take(bytes, pos, 2)?.try_into().unwrap())),
+        "u32" => json!(u32::from_le_bytes(rpc::take(bytes, pos, 4)?.try_into().unwrap())),
+        "u64" => json!(u64::from_le_bytes(rpc::take(bytes, pos, 8)?.try_into().unwrap())),
+        "u128" => json!(u128::from_le_bytes(rpc::take(bytes, pos, 16)?.try_into().unwrap()).to_string()),
+        "u256" => json!(U256::from_le_bytes(&rpc::take(bytes, pos, 32)?.try_into().unwrap()).to_string()),
+        "address" | "0x2::object::ID" | "0x2::object::UID" => {
+            let addr: [u8; 32] = rpc::take(bytes, pos, 32)?.try_into().unwrap();
+            json!(AccountAddress::new(addr).to_hex_literal())
+        }
+        "0x1::string::String" | "0x1::ascii::String" => {
+            let len = rpc::read_uleb128(bytes, pos)?;
+            json!(String::from_utf8_lossy(rpc::take(bytes, pos, len)?))
+        }
+        t if t.starts_with("0x2::balance::Balance<") => decode_value(suitf, bytes, pos, "u64")?,
+        t if t.starts_with("0x1::option::Option<") && t.ends_with('>') => {
+            let inner = &t["0x1::option::Option<".len()..t.len() - 1];
+            match rpc::read_uleb128(bytes, pos)? {
+                0 => Value::Null,
+                _ => decode_value(suitf, bytes, pos, inner)?,
+            }
+        }
+        t if t.starts_with("vector<") && t.ends_with('>') => {
+            let inner = &t["vector<".len()..t.len() - 1];
+            let len = rpc::read_uleb128(bytes, pos)?;
+            let mut elems = Vec::with_capacity(len.min(bytes.len()));
+            for _ in 0..len {
+                elems.push(decode_value(suitf, bytes, pos, inner)?);
+            }
+            Value::Array(elems)
+        }
+        t => decode_struct(suitf, bytes, pos, t)?,
+    };
+    Ok(value)
+}
+
+// Same as `decode_event`, for the contents of a viewed object (`SuiTF::parse_output`):
+// the adapter prints values as text (`10u64`, `fake(2,1)`, nested structs), which are
+// typed here by the layout of `type_`. Text that does not fit the layout is kept as is.
+// Also used to bring JSON values given by authors and players to the same form.
+pub fn decode_contents(
+    suitf: &SuiTF,
+    contents: &Value,
+    type_: &str
+) -> Value {
+    let type_ = type_.trim();
+    let fields = match contents {
+        Value::Object(fields) => fields,
+        Value::Array(elems) => {
+            let inner = type_.strip_prefix("vector<").and_then(|t| t.strip_suffix('>')).unwrap_or_default();
+            return Value::Array(elems.iter().map(|elem| decode_contents(suitf, elem, inner)).collect());
+        }
+        Value::Null => return Value::Null,
+        scalar => return decode_text(suitf, &value_text(scalar), type_),
+    };
+
+    match type_ {
+        "0x2::object::ID" | "0x2::object::UID" => first_text(contents)
+            .map(|text| json!(address_text(suitf, &text)))
+            .unwrap_or(Value::Null),
+        "0x1::string::String" | "0x1::ascii::String" => {
+            let bytes = fields.get("bytes").map(|b| decode_contents(suitf, b, "vector<u8>"));
+            let bytes: Option<Vec<u8>> = bytes
+                .as_ref()
+                .and_then(|b| b.as_array())
+                .and_then(|b| b.iter().map(|byte| byte.as_u64().map(|byte| byte as u8)).collect());
+            match bytes {
+                Some(bytes) => json!(String::from_utf8_lossy(&bytes)),
+                None => contents.clone(),
+            }
+        }
+        t if t.starts_with("0x2::balance::Balance<") => {
+            decode_contents(suitf, fields.get("value").unwrap_or(&Value::Null), "u64")
+        }
+        t if t.starts_with("0x1::option::Option<") && t.ends_with('>') => {
+            let inner = &t["0x1::option::Option<".len()..t.len() - 1];
+            let elems = decode_contents(suitf, fields.get("vec").unwrap_or(&Value::Null), &format!("vector<{}>", inner));
+            elems.as_array().and_then(|elems| elems.first().cloned()).unwrap_or(Value::Null)
+        }
+        t => {
+            let (path, type_args) = match t.split_once('<') {
+                Some((path, args)) => (path, split_type_args(args.strip_suffix('>').unwrap_or(args))),
+                None => (t, Vec::new()),
+            };
+            let struct_ = path.rsplit_once("::").and_then(|(module_path, name)| {
+                let (addr, module) = module_path.split_once("::")?;
+                let addr = AccountAddress::from_hex_literal(addr).ok()?;
+                suitf.get_module_abi(addr, module)?.struct_(name).cloned()
+            });
+
+            let mut decoded = serde_json::Map::new();
+            match struct_ {
+                Some(struct_) => {
+                    for field in struct_.fields.iter() {
+                        let field_type = substitute_type_params(&field.type_, &type_args);
+                        let value = fields.get(&field.name).unwrap_or(&Value::Null);
+                        decoded.insert(field.name.clone(), decode_contents(suitf, value, &field_type));
+                    }
+                }
+                // Framework and unknown structs keep their text values
+                None => {
+                    for (name, value) in fields.iter() {
+                        decoded.insert(name.clone(), decode_contents(suitf, value, ""));
+                    }
+                }
+            }
+            Value::Object(decoded)
+        }
+    }
+}
+
+fn decode_text(
+    suitf: &SuiTF,
+    text: &str,
+    type_: &str
+) -> Value {
+    match type_ {
+        "bool" => text.parse::<bool>().map(Value::Bool).unwrap_or_else(|_| json!(text)),
+        "u8" | "u16" | "u32" | "u64" => {
+            let digits = text.strip_suffix(type_).unwrap_or(text);
+            digits.parse::<u64>().map(|n| json!(n)).unwrap_or_else(|_| json!(text))
+        }
+        "u128" | "u256" => json!(text.strip_suffix(type_).unwrap_or(text)),
+        "address" | "0x2::object::ID" | "0x2::object::UID" => json!(address_text(suitf, text)),
+        t if t.starts_with("vector<") && t.ends_with('>') => {
+            let inner = &t["vector<".len()..t.len() - 1];
+            match text.strip_prefix("vector[").and_then(|elems| elems.strip_suffix(']')) {
+                Some(elems) => Value::Array(
+                    split_type_args(elems).iter().map(|elem| decode_text(suitf, elem, inner)).collect()
+                ),
+                None => json!(text),
+            }
+        }
+        _ => json!(text),
+    }
+}
+
+// Addresses and IDs as hex literals, from `fake(x,y)` placeholders, account and
+// package names or hex
+pub fn address_text(
+    suitf: &SuiTF,
+    text: &str
+) -> String {
+    let text = text.trim().trim_start_matches('@');
+    for prefix in ["fake(", "object("] {
+        if let Some(inner) = text.strip_prefix(prefix).and_then(|t| t.strip_suffix(')')) {
+            if let Some((task, idx)) = inner.split_once(',') {
+                if let (Ok(task), Ok(idx)) = (task.trim().parse(), idx.trim().parse()) {
+                    return AccountAddress::new(rpc::fake_to_object_id(task, idx).into_bytes()).to_hex_literal();
+                }
+            }
+        }
+    }
+    match jsonl::parse_address(suitf, text) {
+        Ok(addr) => addr.to_hex_literal(),
+        Err(_) => text.to_string(),
+    }
+}
+
+// First text value of nested contents, e.g. the ID inside a `UID { id: ID { bytes } }`
+fn first_text(contents: &Value) -> Option<String> {
+    match contents {
+        Value::Object(fields) => fields.values().find_map(first_text),
+        Value::Null => None,
+        scalar => Some(value_text(scalar)),
+    }
+}
+
+fn decode_struct(
+    suitf: &SuiTF,
+    bytes: &[u8],
+    pos: &mut usize,
+    type_: &str
+) -> Result<Value, String> {
+    let (path, type_args) = match type_.split_once('<') {
+        Some((path, args)) => (path, split_type_args(args.strip_suffix('>').unwrap_or(args))),
+        None => (type_, Vec::new()),
+    };
+    let mut parts = path.splitn(3, "::");
+    let (addr, module, name) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(addr), Some(module), Some(name)) => (addr, module, name),
+        _ => return Err(format!("Unsupported type {}", type_)),
+    };
+
+    let addr = AccountAddress::from_hex_literal(addr).map_err(|_| format!("Unsupported type {}", type_))?;
+    let abi = suitf
+        .get_module_abi(addr, module)
+        .ok_or_else(|| format!("Unknown module {}::{}", addr.to_hex_literal(), module))?;
+    let struct_ = abi
+        .struct_(name)
+        .ok_or_else(|| format!("Unknown struct {}", path))?;
+
+    let mut fields = serde_json::Map::new();
+    for field in struct_.fields.iter() {
+        let field_type = substitute_type_params(&field.type_, &type_args);
+        fields.insert(field.name.clone(), decode_value(suitf, bytes, pos, &field_type)?);
+    }
+    Ok(Value::Object(fields))
+}
+
+// Split `A, B<C, D>` at its top-level commas, also used for `fake(1,2), fake(1,3)`
+fn split_type_args(args: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in args.chars() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' | ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        result.push(current.trim().to_string());
+    }
+    result
+}
+
+// Replace the `T0`, `T1`, ... of a field type by the struct's type arguments
+fn substitute_type_params(
+    type_: &str,
+    type_args: &[String]
+) -> String {
+    let mut result = String::new();
+    let mut token = String::new();
+    let flush = |token: &mut String, result: &mut String| {
+        let param = token
+            .strip_prefix('T')
+            .and_then(|idx| idx.parse::<usize>().ok())
+            .and_then(|idx| type_args.get(idx));
+        match param {
+            Some(arg) => result.push_str(arg),
+            None => result.push_str(token),
+        }
+        token.clear();
+    };
+
+    for c in type_.chars() {
+        if matches!(c, '<' | '>' | ',' | ' ') {
+            flush(&mut token, &mut result);
+            result.push(c);
+        } else {
+            token.push(c);
+        }
+    }
+    flush(&mut token, &mut result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::with_session, worker};
+
+    fn decode(
+        suitf: &SuiTF,
+        bytes: &[u8],
+        type_: &str
+    ) -> Result<Value, String> {
+        let mut pos = 0;
+        let value = decode_value(suitf, bytes, &mut pos, type_)?;
+        assert_eq!(pos, bytes.len(), "{} left bytes unread", type_);
+        Ok(value)
+    }
+
+    fn fake_hex(
+        task: u64,
+        idx: u64
+    ) -> String {
+        AccountAddress::new(rpc::fake_to_object_id(task, idx).into_bytes()).to_hex_literal()
+    }
+
+    #[test]
+    fn substitutes_type_params() {
+        let args = vec!["u64".to_string(), "0x2::sui::SUI".to_string()];
+        assert_eq!(substitute_type_params("T0", &args), "u64");
+        assert_eq!(substitute_type_params("vector<T0>", &args), "vector<u64>");
+        assert_eq!(substitute_type_params("0x2::balance::Balance<T1>", &args), "0x2::balance::Balance<0x2::sui::SUI>");
+        assert_eq!(
+            substitute_type_params("0x1::option::Option<vector<T1>>", &args),
+            "0x1::option::Option<vector<0x2::sui::SUI>>"
+        );
+        // Parameters out of range and names that only start with T are kept
+        assert_eq!(substitute_type_params("vector<T2>", &args), "vector<T2>");
+        assert_eq!(substitute_type_params("0x5::m::Token<T0>", &args), "0x5::m::Token<u64>");
+        assert_eq!(substitute_type_params("Tx", &args), "Tx");
+    }
+
+    #[test]
+    fn splits_type_args() {
+        assert_eq!(split_type_args("u8, 0x2::coin::Coin<A, B>"), vec!["u8", "0x2::coin::Coin<A, B>"]);
+        assert_eq!(split_type_args("fake(1,2), fake(1,3)"), vec!["fake(1,2)", "fake(1,3)"]);
+        assert!(split_type_args("").is_empty());
+    }
+
+    #[test]
+    fn decodes_bcs_values() {
+        with_session(|suitf| {
+            assert_eq!(decode(suitf, &[1], "bool").unwrap(), json!(true));
+            assert_eq!(decode(suitf, &[7], "u8").unwrap(), json!(7));
+            assert_eq!(decode(suitf, &[1, 2], "u16").unwrap(), json!(0x0201));
+            assert_eq!(decode(suitf, &5u32.to_le_bytes(), "u32").unwrap(), json!(5));
+            assert_eq!(decode(suitf, &u64::MAX.to_le_bytes(), "u64").unwrap(), json!(u64::MAX));
+            assert_eq!(decode(suitf, &u128::MAX.to_le_bytes(), "u128").unwrap(), json!(u128::MAX.to_string()));
+            assert_eq!(decode(suitf, &[0; 32], "u256").unwrap(), json!("0"));
+
+            let mut address = [0u8; 32];
+            address[31] = 2;
+            assert_eq!(decode(suitf, &address, "address").unwrap(), json!("0x2"));
+            assert_eq!(decode(suitf, &address, "0x2::object::UID").unwrap(), json!("0x2"));
+
+            assert_eq!(decode(suitf, b"\x02hi", "0x1::string::String").unwrap(), json!("hi"));
+            assert_eq!(decode(suitf, &[3, 1, 0, 2, 0, 3, 0], "vector<u16>").unwrap(), json!([1, 2, 3]));
+            assert_eq!(decode(suitf, &[0], "0x1::option::Option<u8>").unwrap(), Value::Null);
+            assert_eq!(decode(suitf, &[1, 9], "0x1::option::Option<u8>").unwrap(), json!(9));
+            assert_eq!(decode(suitf, &10u64.to_le_bytes(), "0x2::balance::Balance<0x2::sui::SUI>").unwrap(), json!(10));
+        });
+    }
+
+    #[test]
+    fn rejects_bad_bcs_values() {
+        with_session(|suitf| {
+            assert!(decode(suitf, &[1, 2], "u64").is_err());
+            assert!(decode(suitf, &[5, 1], "vector<u8>").is_err());
+            assert!(decode(suitf, &[0], "0x5::missing::Struct").unwrap_err().contains("Unknown module"));
+            assert!(decode(suitf, &[0], "Struct").unwrap_err().contains("Unsupported type"));
+        });
+    }
+
+    #[test]
+    fn decodes_contents_text() {
+        with_session(|suitf| {
+            assert_eq!(decode_contents(suitf, &json!("10u64"), "u64"), json!(10));
+            assert_eq!(decode_contents(suitf, &json!("true"), "bool"), json!(true));
+            assert_eq!(decode_contents(suitf, &json!("5u128"), "u128"), json!("5"));
+            assert_eq!(decode_contents(suitf, &json!(3), "u8"), json!(3));
+            assert_eq!(decode_contents(suitf, &Value::Null, "u64"), Value::Null);
+
+            // Addresses from placeholders, account names and hex
+            assert_eq!(decode_contents(suitf, &json!("fake(2,1)"), "address"), json!(fake_hex(2, 1)));
+            let solver = suitf.get_account_address("solver").unwrap().to_hex_literal();
+            assert_eq!(decode_contents(suitf, &json!("solver"), "address"), json!(solver));
+            assert_eq!(decode_contents(suitf, &json!("0x0002"), "address"), json!("0x2"));
+
+            assert_eq!(decode_contents(suitf, &json!("vector[1u8, 2u8]"), "vector<u8>"), json!([1, 2]));
+            assert_eq!(decode_contents(suitf, &json!(["1u64", "2u64"]), "vector<u64>"), json!([1, 2]));
+
+            // Text that does not fit the type is kept
+            assert_eq!(decode_contents(suitf, &json!("abc"), "u64"), json!("abc"));
+        });
+    }
+
+    #[test]
+    fn decodes_contents_structs() {
+        with_session(|suitf| {
+            let uid = json!({ "id": { "bytes": "fake(2,1)" } });
+            assert_eq!(decode_contents(suitf, &uid, "0x2::object::UID"), json!(fake_hex(2, 1)));
+
+            let string = json!({ "bytes": "vector[104u8, 105u8]" });
+            assert_eq!(decode_contents(suitf, &string, "0x1::string::String"), json!("hi"));
+
+            let balance = json!({ "value": "7u64" });
+            assert_eq!(decode_contents(suitf, &balance, "0x2::balance::Balance<0x2::sui::SUI>"), json!(7));
+
+            let none = json!({ "vec": "vector[]" });
+            assert_eq!(decode_contents(suitf, &none, "0x1::option::Option<u64>"), Value::Null);
+            let some = json!({ "vec": "vector[5u64]" });
+            assert_eq!(decode_contents(suitf, &some, "0x1::option::Option<u64>"), json!(5));
+
+            // Structs without a known layout keep their text
+            let unknown = json!({ "a": "1u8", "b": { "c": "fake(2,1)" } });
+            assert_eq!(decode_contents(suitf, &unknown, "0x5::m::S"), unknown);
+        });
+    }
+
+    #[test]
+    fn compares_types_with_addresses() {
+        with_session(|suitf| {
+            assert!(output::type_matches(suitf, "sui::coin::Coin<sui::sui::SUI>", "0x2::coin::Coin<0x2::sui::SUI>"));
+            assert!(output::type_matches(
+                suitf,
+                "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI",
+                "sui::sui::SUI"
+            ));
+            assert!(output::type_matches(suitf, &format!("{}::m::S", fake_hex(2, 0)), "fake(2,0)::m::S"));
+
+            // Same module and name in another package is another type
+            assert!(!output::type_matches(suitf, "0x5::sui::SUI", "0x2::sui::SUI"));
+            assert!(!output::type_matches(suitf, "0x2::coin::Coin<0x5::sui::SUI>", "0x2::coin::Coin<0x2::sui::SUI>"));
+
+            assert_eq!(output::coin_inner_type("sui::coin::Coin<sui::sui::SUI>"), Some("sui::sui::SUI"));
+            assert_eq!(output::coin_inner_type("0x2::coin::Coin<0x5::c::C>"), Some("0x5::c::C"));
+            assert_eq!(output::coin_inner_type("0x5::coin::Coin<0x2::sui::SUI>"), None);
+        });
+    }
+
+    #[test]
+    fn builds_view_transactions() {
+        with_session(|suitf| {
+            let call = CallSpec::new("0x5", "is_solved")
+                .module("challenge")
+                .args(vec![json!({ "object": [2, 1] }), json!(7)])
+                .type_args(vec!["0x2::sui::SUI".to_string()]);
+            let (inputs, commands) = view_transaction(suitf, &call).unwrap();
+            assert_eq!(inputs.len(), 3);
+            assert_eq!(commands, vec![
+                format!(
+                    "0x5::challenge::is_solved<{}>(Input(0), Input(1))",
+                    "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI"
+                ),
+                "0x2::vec_set::singleton<bool>(Input(2))".to_string(),
+                "0x2::vec_set::insert<bool>(Result(1), Result(0))".to_string(),
+            ]);
+
+            // Without arguments the guard is the only input
+            let (inputs, commands) = view_transaction(suitf, &CallSpec::new("0x5", "done").module("m")).unwrap();
+            assert_eq!(inputs.len(), 1);
+            assert_eq!(commands[0], "0x5::m::done()");
+            assert_eq!(commands[1], "0x2::vec_set::singleton<bool>(Input(0))");
+        });
+    }
+
+    #[test]
+    fn not_passes_errors_up() {
+        let balance = |account: &str, amount: u64| WinCondition::Balance {
+            account: account.to_string(),
+            coin_type: default_coin_type(),
+            op: CompareOp::Ge,
+            amount,
+        };
+        let not = |condition: &WinCondition| WinCondition::Not { condition: Box::new(condition.clone()) };
+        let holds = balance("solver", 0);
+        let fails = balance("solver", u64::MAX);
+        // Unknown accounts cannot be checked
+        let broken = balance("nobody", 0);
+
+        worker::block_on_local(async {
+            let mut suitf = SuiTF::initialize(Vec::new(), Some(vec!["solver".to_string()])).await.unwrap();
+            assert!(matches!(not(&holds).evaluate(&mut suitf).await, Err(Unmet::Fails(_))));
+            assert_eq!(not(&fails).evaluate(&mut suitf).await, Ok(()));
+            assert!(matches!(not(&broken).evaluate(&mut suitf).await, Err(Unmet::Error(_))));
+
+            let all = WinCondition::All { conditions: vec![fails.clone(), broken.clone()] };
+            assert!(matches!(all.evaluate(&mut suitf).await, Err(Unmet::Fails(_))));
+            let any = WinCondition::Any { conditions: vec![fails.clone(), broken.clone()] };
+            assert!(matches!(any.evaluate(&mut suitf).await, Err(Unmet::Error(_))));
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::thread;
+
+use move_compiler::shared::NumericalAddress;
+use move_core_types::{account_address::AccountAddress, language_storage::TypeTag};
+use move_transactional_test_runner::framework::MaybeNamedCompiledModule;
+use sui_transactional_test_runner::{args::SuiValue, test_adapter::FakeID};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{abort::MoveAbort, win::{Unmet, WinCondition}, SuiTF};
+
+// `SuiTF` is not `Send`: the adapter spawns local tasks and keeps `Rc`s around. A worker
+// thread owns one environment and runs jobs sent by `SuiHandle`s, which are `Send` and
+// `Clone` and can be used from any task of a multi-threaded runtime:
+//
+//   let handle = SuiHandle::initialize(named_addresses, Some(accounts)).await?;
+//   tokio::spawn(async move {
+//       let output = handle.call_function(addr, "pool", "swap", args, Vec::new(), Some("solver".to_string())).await;
+//   });
+//
+// Jobs run one after the other. The worker stops once every handle is dropped.
+type Job = Box<dyn for<'a> FnOnce(&'a mut SuiTF) -> Pin<Box<dyn Future<Output = ()> + 'a>> + Send>;
+
+#[derive(Clone)]
+pub struct SuiHandle {
+    sender: mpsc::UnboundedSender<Job>,
+}
+
+// Errors cross the channel as text, except aborts which stay typed
+enum Failure {
+    Abort(MoveAbort),
+    Other(String),
+}
+
+impl From<Box<dyn Error>> for Failure {
+    fn from(err: Box<dyn Error>) -> Self {
+        match err.downcast::<MoveAbort>() {
+            Ok(abort) => Failure::Abort(*abort),
+            Err(err) => Failure::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<Failure> for Box<dyn Error> {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Abort(abort) => Box::new(abort),
+            Failure::Other(text) => text.into(),
+        }
+    }
+}
+
+// Run a future that needs a `LocalSet` (anything driving a `SuiTF`) to completion on
+// a current-thread runtime of the calling thread
+pub fn block_on_local<F: Future>(future: F) -> Result<F::Output, Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start a worker runtime: {}", e))?;
+    let local = tokio::task::LocalSet::new();
+    Ok(runtime.block_on(local.run_until(future)))
+}
+
+impl SuiHandle {
+    // Start a worker whose environment comes from `SuiTF::initialize`
+    pub async fn initialize(
+        named_addresses: Vec<(String, NumericalAddress)>,
+        accounts: Option<Vec<String>>
+    ) -> Result<SuiHandle, Box<dyn Error>> {
+        SuiHandle::spawn(move || SuiTF::initialize(named_addresses, accounts)).await
+    }
+
+    // Start a worker whose environment is built by `init` on the worker thread, e.g.
+    // `|| async move { let mut suitf = manifest.initialize().await?; manifest.setup(&mut suitf).await?; Ok(suitf) }`
+    pub async fn spawn<F, Fut>(init: F) -> Result<SuiHandle, Box<dyn Error>>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<SuiTF, Box<dyn Error>>> + 'static,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Job>();
+        let (ready_sender, ready) = oneshot::channel::<Result<(), String>>();
+
+        thread::Builder::new()
+            .name("suitf-worker".to_string())
+            .spawn(move || {
+                let worker = async move {
+                    let mut suitf = match init().await {
+                        Ok(suitf) => suitf,
+                        Err(e) => {
+                            let _ = ready_sender.send(Err(e.to_string()));
+                            return;
+                        }
+                    };
+                    let _ = ready_sender.send(Ok(()));
+                    while let Some(job) = receiver.recv().await {
+                        job(&mut suitf).await;
+                    }
+                };
+                if let Err(e) = block_on_local(worker) {
+                    tracing::error!(error = %e, "worker failed to start");
+                }
+            })
+            .map_err(|e| format!("Failed to start a worker thread: {}", e))?;
+
+        ready.await.map_err(|_| "The worker stopped during initialization")??;
+        Ok(SuiHandle { sender })
+    }
+
+    // Run `job` against the environment on the worker thread and give its result
+    pub async fn run<R, F>(&self, job: F) -> Result<R, Box<dyn Error>>
+    where
+        R: Send + 'static,
+        F: for<'a> FnOnce(&'a mut SuiTF) -> Pin<Box<dyn Future<Output = R> + 'a>> + Send + 'static,
+    {
+        let (result_sender, result) = oneshot::channel();
+        let job: Job = Box::new(move |suitf| Box::pin(async move {
+            let _ = result_sender.send(job(suitf).await);
+        }));
+        self.sender.send(job).map_err(|_| "The worker has stopped")?;
+        Ok(result.await.map_err(|_| "The worker stopped while running a job")?)
+    }
+
+    // Same as `run`, for jobs failing with `Box<dyn Error>`
+    async fn try_run<R, F>(&self, job: F) -> Result<R, Box<dyn Error>>
+    where
+        R: Send + 'static,
+        F: for<'a> FnOnce(&'a mut SuiTF) -> Pin<Box<dyn Future<Output = Result<R, Box<dyn Error>>> + 'a>> + Send + 'static,
+    {
+        self.run(move |suitf| Box::pin(async move { job(suitf).await.map_err(Failure::from) }))
+            .await?
+            .map_err(Into::into)
+    }
+
+    pub async fn publish_compiled_module(
+        &self,
+        modules: Vec<MaybeNamedCompiledModule>,
+        module_dependencies: Vec<String>,
+        sender: Option<String>
+    ) -> Result<AccountAddress, Box<dyn Error>> {
+        self.try_run(move |suitf| Box::pin(suitf.publish_compiled_module(modules, module_dependencies, sender))).await
+    }
+
+    pub async fn call_function(
+        &self,
+        mod_addr: AccountAddress,
+        mod_name: &str,
+        fun_name: &str,
+        args: Vec<SuiValue>,
+        type_args: Vec<TypeTag>,
+        signer: Option<String>
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let (mod_name, fun_name) = (mod_name.to_string(), fun_name.to_string());
+        self.try_run(move |suitf| Box::pin(async move {
+            suitf.call_function(mod_addr, &mod_name, &fun_name, args, type_args, signer).await
+        })).await
+    }
+
+    pub async fn view_object(
+        &self,
+        id: FakeID
+    ) -> Result<Option<serde_json::Value>, Box<dyn Error>> {
+        self.try_run(move |suitf| Box::pin(suitf.view_object(id))).await
+    }
+
+    pub async fn alias(
+        &self,
+        name: &str,
+        id: FakeID
+    ) -> Result<(), Box<dyn Error>> {
+        let name = name.to_string();
+        self.try_run(move |suitf| Box::pin(async move { suitf.alias(&name, id) })).await
+    }
+
+    pub async fn fund_account(
+        &self,
+        account_address: String,
+        amount: u64,
+        sender: AccountAddress
+    ) -> Result<(), Box<dyn Error>> {
+        self.try_run(move |suitf| Box::pin(suitf.fund_account(account_address, amount, sender))).await
+    }
+
+    pub async fn get_account_address(
+        &self,
+        account_name: &str
+    ) -> Result<Option<AccountAddress>, Box<dyn Error>> {
+        let account_name = account_name.to_string();
+        self.run(move |suitf| Box::pin(async move { suitf.get_account_address(&account_name) })).await
+    }
+
+    pub async fn get_package_address(
+        &self,
+        package_name: &str
+    ) -> Result<Option<AccountAddress>, Box<dyn Error>> {
+        let package_name = package_name.to_string();
+        self.run(move |suitf| Box::pin(async move { suitf.get_package_address(&package_name) })).await
+    }
+
+    // `Ok(Err(reason))` when the condition is not met
+    pub async fn check_win(
+        &self,
+        condition: WinCondition
+    ) -> Result<Result<(), Unmet>, Box<dyn Error>> {
+        self.run(move |suitf| Box::pin(async move { suitf.check_win(&condition).await })).await
+    }
+}